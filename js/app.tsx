@@ -2,11 +2,27 @@ import React from 'react';
 import { renderToStaticMarkup } from "react-dom"
 import Root from "./root";
 // @ts-expect-error
-import Route from "$route";
-import { RouteContext } from '$lib';
+import * as route from "$route";
+// @ts-expect-error
+import layouts from "$layouts";
+import { Layout, Meta, RouteContext } from '$lib';
+
+const Route = route.default;
+
+const defaults: Meta = globalThis.routeMeta;
+// not `route.meta`, the bundler turns that into an undefined variable for
+// routes without a `meta` export
+const meta: Meta = { ...defaults, ...route["meta"]?.(globalThis.routeData, defaults) };
+
+// the innermost layout is the last one, it wraps the route directly
+const page = (layouts as Layout[]).reduceRight(
+  (children, Layout) => <Layout>{children}</Layout>,
+  <Route />
+);
 
+// the value of the bundle, the server sends it as the page
 renderToStaticMarkup(
   <RouteContext.Provider value={globalThis.routeData}>
-    <Root children={<Route />} />
+    <Root meta={meta} children={page} />
   </RouteContext.Provider>
-)
\ No newline at end of file
+)
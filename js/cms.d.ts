@@ -0,0 +1,27 @@
+// Helpers implemented by the server, see `src/host/mod.rs`.
+declare const cms: {
+  /** Public URL of a post, e.g. one from a `loader`, `undefined` if there's no such post type */
+  url(post: { slug: string, type: string }): string | undefined
+
+  /**
+   * Format a date with a strftime format, `%Y-%m-%d` by default,
+   * e.g. `cms.formatDate(post.created_at, "%d %B %Y")`
+   */
+  formatDate(date: Date | string | number, format?: string): string
+
+  /** Fingerprinted URL of a file in `public/`, e.g. `cms.asset("site.css")` -> `/assets/site.3f2a1c4b.css` */
+  asset(path: string): string
+
+  /**
+   * Message for `key` from `translations/<locale>.json`, with `{name}` replaced by `vars.name`,
+   * e.g. `cms.t("search.results", { count: 3 })`. The key itself when it's missing.
+   */
+  t(key: string, vars?: Record<string, string | number>): string
+
+  readonly env: {
+    environment: "development" | "production"
+    locale: string
+    /** version of the server */
+    version: string
+  }
+}
@@ -0,0 +1,11 @@
+import React from 'react'
+import styles from './CallToAction.module.css'
+
+export default function CallToAction({ label, href, text }: { label: string, href: string, text?: string }) {
+  return (
+    <section className={styles.callToAction}>
+      {text && <p>{text}</p>}
+      <a className={styles.button} href={href}>{label}</a>
+    </section>
+  )
+}
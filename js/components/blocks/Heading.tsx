@@ -0,0 +1,6 @@
+import React from 'react'
+
+export default function Heading({ text, level = 2 }: { text: string, level?: number }) {
+  const Tag = `h${Math.min(Math.max(Math.round(level), 1), 6)}` as keyof JSX.IntrinsicElements;
+  return <Tag>{text}</Tag>
+}
@@ -0,0 +1,10 @@
+import React from 'react'
+
+export default function Image({ src, alt = "", caption }: { src: string, alt?: string, caption?: string }) {
+  return (
+    <figure>
+      <img src={src} alt={alt} />
+      {caption && <figcaption>{caption}</figcaption>}
+    </figure>
+  )
+}
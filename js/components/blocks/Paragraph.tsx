@@ -0,0 +1,5 @@
+import React from 'react'
+
+export default function Paragraph({ text }: { text: string }) {
+  return <p>{text}</p>
+}
@@ -0,0 +1,10 @@
+import React from 'react'
+
+export default function Quote({ text, cite }: { text: string, cite?: string }) {
+  return (
+    <blockquote>
+      <p>{text}</p>
+      {cite && <cite>{cite}</cite>}
+    </blockquote>
+  )
+}
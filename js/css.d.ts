@@ -0,0 +1,7 @@
+// Stylesheets are bundled by the server, see `src/tsx/css.rs`.
+declare module '*.module.css' {
+  const classes: { readonly [name: string]: string };
+  export default classes;
+}
+
+declare module '*.css';
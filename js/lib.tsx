@@ -1,29 +1,210 @@
-import { createContext, useContext } from 'react';
+import React, { createContext, useContext } from 'react';
+import Heading from '$components/blocks/Heading';
+import Paragraph from '$components/blocks/Paragraph';
+import Image from '$components/blocks/Image';
+import Quote from '$components/blocks/Quote';
+import Embed from '$components/blocks/Embed';
+import CallToAction from '$components/blocks/CallToAction';
+import Test from '$components/Test';
 
 export const RouteContext = createContext(globalThis.routeData);
 export function useRouteData<T = any>(): T {
   return useContext(RouteContext);
 }
 
+/** Fingerprinted URL of a file in `public/`, e.g. `asset("site.css")` -> `/assets/site.3f2a1c4b.css` */
+export function asset(path: string): string {
+  return cms.asset(path);
+}
+
+/** URLs of the stylesheets built from the CSS imported by the current route */
+export function stylesheets(): string[] {
+  return globalThis.routeStylesheets ?? [];
+}
+
+/**
+ * What goes in the `<head>` of a page. The server fills in defaults, e.g. from
+ * the title, content and first image of a post, see `PageMeta` in `meta/mod.rs`.
+ */
+export interface Meta {
+  siteName: string
+  /** `lang` of the document, e.g. "en", the `Content-Language` header stays the one of the site */
+  lang: string
+  /** without the name of the site, which is added to it */
+  title?: string
+  description?: string
+  /** absolute URL of the page */
+  canonical?: string
+  /** absolute URL of an image representing the page */
+  image?: string
+  /** Open Graph type, e.g. "website" or "article" */
+  type: string
+}
+
+/**
+ * Export as `meta` from a route to change what goes in its `<head>`,
+ * e.g. `export const meta: MetaFunction<PostPage> = post => ({ title: post.title })`.
+ * It runs before the route renders, since the `<head>` is sent first.
+ */
+export type MetaFunction<T = any> = (data: T, defaults: Meta) => Partial<Meta>
+
+/** `<title>`, description, canonical link and Open Graph tags for `meta` */
+export function MetaTags({ meta }: { meta: Meta }) {
+  const title = meta.title ? `${meta.title} | ${meta.siteName}` : meta.siteName;
+
+  return (
+    <>
+      <title>{title}</title>
+      {meta.description && <meta name="description" content={meta.description} />}
+      {meta.canonical && <link rel="canonical" href={meta.canonical} />}
+      <meta property="og:title" content={meta.title ?? meta.siteName} />
+      <meta property="og:site_name" content={meta.siteName} />
+      <meta property="og:type" content={meta.type} />
+      {meta.description && <meta property="og:description" content={meta.description} />}
+      {meta.canonical && <meta property="og:url" content={meta.canonical} />}
+      {meta.image && <meta property="og:image" content={meta.image} />}
+      <meta name="twitter:card" content={meta.image ? "summary_large_image" : "summary"} />
+    </>
+  )
+}
+
+/**
+ * Default export of a `_layout.tsx`, which wraps every route in its directory
+ * and below, inside the layouts of the directories above it.
+ */
+export type Layout = React.ComponentType<{ children: React.ReactNode }>
+
+/** Route data of a page served by the file-system router, e.g. `js/routes/blog/[slug].tsx` */
+export interface PageData {
+  path: string
+  params: Record<string, string>
+}
+
+/** Route data of `404.tsx` and `500.tsx`, see `ErrorPageData` in `routers/error_pages.rs` */
+export interface ErrorPageData {
+  /** path of the request that failed */
+  path: string
+  status: number
+  /** what went wrong, only while developing */
+  error: {
+    message: string
+    /** `file:line:column` in the TSX sources, if known */
+    location: string | null
+    stack: string
+  } | null
+}
+
+/** A fixed value, or a param of the page, e.g. `{ param: "slug" }` for `blog/[slug].tsx` */
+export type LoaderArgument = string | { param: string }
+
+/** Read-only queries a route can ask for, see `LoaderQuery` in `loader/mod.rs` */
+export type LoaderQuery =
+  | { query: "posts", type?: LoaderArgument, sort?: Posts["sort"], order?: Posts["order"], limit?: number }
+  | { query: "post", slug: LoaderArgument, type?: LoaderArgument }
+  | { query: "postTypes" }
+  | { query: "media", limit?: number }
+
+/**
+ * Data loaded on the server before a route renders, merged into its route data
+ * under the same names. Export it as a literal, it's read when the route is bundled:
+ * `export const loader: Loader = { latestPosts: { query: "posts", limit: 5 } }`
+ */
+export type Loader = Record<string, LoaderQuery>
+
 export interface Post {
   id: string
   title: string
   content: string
   status: "draft" | "published"
+  created_at?: string
+  blocks?: Block[]
+  /** `content` rendered and sanitized on save */
+  rendered?: RenderedMarkdown
   type: string
 }
 
+/** A piece of structured content, validated against `BlockRegistry` on the server */
+export interface Block {
+  type: string
+  [field: string]: any
+}
+
+const blockComponents: Record<string, React.ComponentType<any>> = {
+  heading: Heading,
+  paragraph: Paragraph,
+  image: Image,
+  quote: Quote,
+  embed: Embed,
+  call_to_action: CallToAction,
+};
+
+/** Components usable in a `component` block, keep in sync with `with_component` in `main.rs` */
+const customComponents: Record<string, React.ComponentType<any>> = {
+  Test,
+};
+
+export function Blocks({ blocks }: { blocks: Block[] }) {
+  return (
+    <>
+      {blocks.map(({ type, ...fields }, index) => {
+        if (type === "component") {
+          const Component = customComponents[fields.name];
+          return Component ? <Component key={index} {...fields.props} /> : null;
+        }
+
+        const Component = blockComponents[type];
+        return Component ? <Component key={index} {...fields} /> : null;
+      })}
+    </>
+  )
+}
+
+
+export interface TocEntry {
+  level: number
+  id: string
+  title: string
+}
+
+export interface RenderedMarkdown {
+  /** sanitized HTML rendered from the Markdown `content` */
+  html: string
+  toc: TocEntry[]
+}
+
+export interface PostPage extends Post {
+  rendered: RenderedMarkdown
+}
+
+export interface SanitizePolicy {
+  tags: string[]
+  generic_attributes: string[]
+  tag_attributes: Record<string, string[]>
+  tag_classes: Record<string, string[]>
+  tag_styles: Record<string, string[]>
+  url_schemes: string[]
+}
 
 export interface PostType {
   id: string,
   singular: string,
   plural: string,
   path_prefix?: string,
+  sanitize?: SanitizePolicy,
 }
 
 export interface Posts {
   posts: Post[]
   post_type: PostType
+  total: number
+  page: number
+  per_page: number
+  total_pages: number
+  sort: "title" | "created_at" | "status"
+  order: "asc" | "desc"
+  status: Post["status"] | null
+  search: string | null
+  errors: FlashError[]
 }
 
 
@@ -31,3 +212,77 @@ export interface FlashError {
   level: "error" | "warning" | "info" | "success"
   message: string
 }
+
+export interface SearchResult {
+  id: string
+  title: string
+  slug: string
+  status: Post["status"]
+  type: string
+  score: number
+  /** HTML excerpt with matched terms wrapped in `<b>` */
+  snippet: string
+  url: string
+}
+
+export interface SearchResults {
+  query: string
+  results: SearchResult[]
+}
+
+export interface Media {
+  id: string
+  filename: string
+  key: string
+  mime_type: string
+  size: number
+  width?: number
+  height?: number
+  created_at?: string
+}
+
+export function mediaUrl(media: Media): string {
+  return `/uploads/${media.key}`;
+}
+
+/** Sizes the `/media/:id` endpoint accepts, keep in sync with `ALLOWED_SIZES` in `resize.rs` */
+export const IMAGE_WIDTHS = [160, 320, 640, 960, 1280, 1920];
+
+export interface ImageOptions {
+  w?: number
+  h?: number
+  fit?: "contain" | "cover"
+  fmt?: "webp" | "png" | "jpeg"
+}
+
+/** URL of a resized and/or converted version of an image */
+export function imageUrl(media: Media, options: ImageOptions = {}): string {
+  const id = media.id.slice(media.id.indexOf(":") + 1);
+  const query = Object.entries(options)
+    .filter(([, value]) => value !== undefined)
+    .map(([key, value]) => `${key}=${encodeURIComponent(String(value))}`)
+    .join("&");
+
+  return query ? `/media/${encodeURIComponent(id)}?${query}` : mediaUrl(media);
+}
+
+/**
+ * `srcset` attribute offering every allowed width up to the original width,
+ * e.g. `<img src={mediaUrl(media)} srcSet={srcset(media, { fmt: "webp" })} sizes="50vw" />`
+ */
+export function srcset(media: Media, options: Omit<ImageOptions, "w" | "h"> = {}): string {
+  if (!media.width) {
+    return "";
+  }
+
+  const widths = IMAGE_WIDTHS.filter(width => width < media.width!);
+  return widths
+    .map(width => `${imageUrl(media, { ...options, w: width })} ${width}w`)
+    .concat(`${options.fmt ? imageUrl(media, options) : mediaUrl(media)} ${media.width}w`)
+    .join(", ");
+}
+
+export interface MediaLibrary {
+  media: Media[]
+  errors: FlashError[]
+}
@@ -1,10 +1,16 @@
 import React from 'react';
+import { asset, Meta, MetaTags, stylesheets } from '$lib';
 
-export default function Root({ children }: { children: React.ReactNode }) {
+export default function Root({ meta, children }: { meta: Meta, children: React.ReactNode }) {
   return (
-    <html>
+    <html lang={meta.lang}>
       <head>
-        <title>Hello World</title>
+        <meta charSet="utf-8" />
+        <meta name="viewport" content="width=device-width, initial-scale=1" />
+        <MetaTags meta={meta} />
+        <link rel="icon" href={asset("favicon.svg")} />
+        <link rel="stylesheet" href={asset("site.css")} />
+        {stylesheets().map(href => <link key={href} rel="stylesheet" href={href} />)}
       </head>
       <body>
         {children}
@@ -0,0 +1,18 @@
+import { ErrorPageData, MetaFunction, useRouteData } from '$lib'
+import React from 'react'
+
+export const meta: MetaFunction<ErrorPageData> = () => ({
+  title: cms.t("errors.notFound"),
+});
+
+export default function NotFound() {
+  const data = useRouteData<ErrorPageData>();
+
+  return (
+    <div>
+      <h1>{cms.t("errors.notFound")}</h1>
+      <p>{cms.t("errors.notFoundText", { path: data.path })}</p>
+      <a href="/">{cms.t("layout.home")}</a>
+    </div>
+  )
+}
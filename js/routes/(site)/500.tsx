@@ -0,0 +1,27 @@
+import { ErrorPageData, MetaFunction, useRouteData } from '$lib'
+import React from 'react'
+
+export const meta: MetaFunction<ErrorPageData> = () => ({
+  title: cms.t("errors.serverError"),
+});
+
+export default function ServerError() {
+  const data = useRouteData<ErrorPageData>();
+
+  return (
+    <div>
+      <h1>{cms.t("errors.serverError")}</h1>
+      <p>{cms.t("errors.serverErrorText")}</p>
+
+      {data.error && (
+        <>
+          <p>
+            {data.error.message}
+            {data.error.location && <> at <code>{data.error.location}</code></>}
+          </p>
+          {data.error.stack && <pre>{data.error.stack}</pre>}
+        </>
+      )}
+    </div>
+  )
+}
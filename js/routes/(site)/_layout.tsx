@@ -0,0 +1,21 @@
+import React from 'react'
+
+export default function SiteLayout({ children }: { children: React.ReactNode }) {
+  return (
+    <>
+      <header>
+        <nav>
+          <a href="/">{cms.t("layout.home")}</a>
+          {" "}
+          <a href="/search">{cms.t("layout.search")}</a>
+        </nav>
+      </header>
+
+      <main>{children}</main>
+
+      <footer>
+        <small>{cms.t("layout.poweredBy", { version: cms.env.version })}</small>
+      </footer>
+    </>
+  )
+}
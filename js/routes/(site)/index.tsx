@@ -0,0 +1,32 @@
+import { Loader, PageData, Post, useRouteData } from '$lib'
+import React from 'react'
+
+export const loader: Loader = {
+  latestPosts: { query: 'posts', limit: 5 },
+};
+
+interface HomeData extends PageData {
+  latestPosts: Post[]
+}
+
+export default function Home() {
+  const data = useRouteData<HomeData>();
+
+  return (
+    <div>
+      <h1>{cms.t("home.title")}</h1>
+
+      <h2>{cms.t("home.latestPosts")}</h2>
+      <ul>
+        {data.latestPosts.map(post => (
+          <li key={post.id}>
+            <a href={cms.url(post) ?? "#"}>{post.title}</a>
+            {post.created_at && <small> {cms.formatDate(post.created_at, "%d %B %Y")}</small>}
+          </li>
+        ))}
+      </ul>
+
+      <a href="/admin">{cms.t("home.admin")}</a>
+    </div>
+  )
+}
@@ -0,0 +1,35 @@
+import { Blocks, PostPage, useRouteData } from '$lib'
+import React from 'react'
+
+export default function Post() {
+  const data = useRouteData<PostPage>();
+
+  return (
+    <article>
+      <h1>{data.title}</h1>
+      {data.created_at && (
+        <p>
+          <time dateTime={data.created_at}>
+            {cms.t("post.publishedOn", { date: cms.formatDate(data.created_at, "%d %B %Y") })}
+          </time>
+        </p>
+      )}
+
+      {data.rendered.toc.length > 0 && (
+        <nav>
+          <ul>
+            {data.rendered.toc.map(entry => (
+              <li key={entry.id} style={{ marginLeft: `${entry.level - 1}em` }}>
+                <a href={`#${entry.id}`}>{entry.title}</a>
+              </li>
+            ))}
+          </ul>
+        </nav>
+      )}
+
+      {data.blocks
+        ? <Blocks blocks={data.blocks} />
+        : <div dangerouslySetInnerHTML={{ __html: data.rendered.html }} />}
+    </article>
+  )
+}
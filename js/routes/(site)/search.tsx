@@ -0,0 +1,32 @@
+import { MetaFunction, SearchResults, useRouteData } from '$lib'
+import React from 'react'
+
+export const meta: MetaFunction<SearchResults> = data => ({
+  title: data.query ? `Search: ${data.query}` : "Search",
+});
+
+export default function Search() {
+  const data = useRouteData<SearchResults>();
+
+  return (
+    <div>
+      <h1>Search</h1>
+
+      <form action="/search" method="get">
+        <input type="search" name="q" placeholder="Search" defaultValue={data.query} />
+        <button type="submit">Search</button>
+      </form>
+
+      {data.query && data.results.length === 0 && <p>No results for "{data.query}"</p>}
+
+      <ol>
+        {data.results.map(result => (
+          <li key={result.id}>
+            <a href={result.url}>{result.title}</a>
+            {result.snippet && <p dangerouslySetInnerHTML={{ __html: result.snippet }} />}
+          </li>
+        ))}
+      </ol>
+    </div>
+  )
+}
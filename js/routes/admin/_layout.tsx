@@ -0,0 +1,21 @@
+import React from 'react'
+
+export default function AdminLayout({ children }: { children: React.ReactNode }) {
+  return (
+    <div style={{ display: 'flex', gap: '2em' }}>
+      <nav>
+        <ul>
+          <li><a href="/admin">Overview</a></li>
+          <li><a href="/admin/media">Media</a></li>
+        </ul>
+
+        <form action="/admin/search" method="get">
+          <input type="search" name="q" placeholder="Search all content" />
+          <button type="submit">Search</button>
+        </form>
+      </nav>
+
+      <div style={{ flex: 1 }}>{children}</div>
+    </div>
+  )
+}
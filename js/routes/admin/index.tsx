@@ -6,6 +6,8 @@ export default function Admin() {
 
   return (
     <div>
+      <h1>Overview</h1>
+
       {data.map(type => (
         <div key={type.id}>
           <a href={`/admin/posts?type=${type.id}`}>
@@ -0,0 +1,52 @@
+import { MediaLibrary, mediaUrl, srcset, useRouteData } from '$lib'
+import React from 'react'
+
+function formatSize(bytes: number) {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+}
+
+export default function Media() {
+  const data = useRouteData<MediaLibrary>();
+
+  return (
+    <div>
+      <h1>Media</h1>
+
+      <form action="/admin/media" method="post" encType="multipart/form-data">
+        <input type="file" name="file" multiple />
+        <button type="submit">Upload</button>
+      </form>
+
+      {data.errors.map(error => (
+        <ul>
+          <li><b>{error.level}</b>: {error.message}</li>
+        </ul>
+      ))}
+
+      <div style={{ display: 'flex', flexWrap: 'wrap', gap: '1em' }}>
+        {data.media.map(item => (
+          <figure key={item.id} style={{ width: 160 }}>
+            {item.mime_type.startsWith("image/") && item.width
+              ? <img
+                src={mediaUrl(item)}
+                srcSet={srcset(item, { fmt: "webp" })}
+                sizes="160px"
+                alt={item.filename}
+                style={{ maxWidth: '100%' }}
+                loading="lazy"
+              />
+              : <div>{item.mime_type}</div>}
+            <figcaption>
+              <a href={mediaUrl(item)}>{item.filename}</a>
+              <br />
+              {formatSize(item.size)}
+              {item.width && item.height && <> &middot; {item.width}&times;{item.height}</>}
+            </figcaption>
+          </figure>
+        ))}
+      </div>
+    </div>
+  )
+}
@@ -1,19 +1,86 @@
-import { Post, Posts, useRouteData } from '$lib'
+import { Posts, useRouteData } from '$lib'
 import React from 'react'
 
 export default function Posts() {
   const data = useRouteData<Posts>();
 
+  // builds a link to this page keeping the current filters, overridden by `params`
+  const href = (params: Partial<Record<"page" | "sort" | "order", string | number>>) => {
+    const query: Record<string, string | number | null | undefined> = {
+      type: data.post_type.id,
+      per_page: data.per_page,
+      sort: data.sort,
+      order: data.order,
+      status: data.status,
+      search: data.search,
+      ...params,
+    };
+
+    return "/admin/posts?" + Object.entries(query)
+      .filter(([, value]) => value != null)
+      .map(([key, value]) => `${encodeURIComponent(key)}=${encodeURIComponent(String(value))}`)
+      .join("&");
+  };
+
+  const sortLink = (sort: Posts["sort"], label: string) => {
+    const order = data.sort === sort && data.order === "asc" ? "desc" : "asc";
+    const arrow = data.sort === sort ? (data.order === "asc" ? " ▲" : " ▼") : "";
+    return <a href={href({ sort, order, page: 1 })}>{label}{arrow}</a>;
+  };
+
   return (
     <div>
-      <a href="/admin">Back to overview</a>
       <h1>{data.post_type.plural} - <a href={`/admin/posts/create?type=${data.post_type.id}`}>Create {data.post_type.singular}</a></h1>
+
+      <form action="/admin/posts" method="get">
+        <input type="hidden" name="type" value={data.post_type.id} />
+        <input type="hidden" name="sort" value={data.sort} />
+        <input type="hidden" name="order" value={data.order} />
+        <input type="search" name="search" placeholder="Search titles" defaultValue={data.search ?? ""} />
+        <select name="status" defaultValue={data.status ?? ""}>
+          <option value="">All statuses</option>
+          <option value="published">Published</option>
+          <option value="draft">Draft</option>
+        </select>
+        <select name="per_page" defaultValue={String(data.per_page)}>
+          {[10, 20, 50, 100].map(size => (
+            <option key={size} value={size}>{size} per page</option>
+          ))}
+        </select>
+        <button type="submit">Filter</button>
+      </form>
+
+      {data.errors.map(error => (
+        <ul>
+          <li><b>{error.level}</b>: {error.message}</li>
+        </ul>
+      ))}
+
+      <p>{data.total} {data.total === 1 ? data.post_type.singular : data.post_type.plural}</p>
+
+      <table>
+        <thead>
+          <tr>
+            <th>{sortLink("title", "Title")}</th>
+            <th>{sortLink("status", "Status")}</th>
+            <th>{sortLink("created_at", "Created")}</th>
+          </tr>
+        </thead>
+        <tbody>
+          {data.posts.map(item => (
+            <tr key={item.id}>
+              <td>{item.title}</td>
+              <td>{item.status}</td>
+              <td>{item.created_at}</td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+
       <div>
-        {data.posts.map(item => (
-          <div key={item.id}>
-            {JSON.stringify(item)}
-          </div>
-        ))}
+        {data.page > 1 && <a href={href({ page: data.page - 1 })}>Previous</a>}
+        {" "}Page {data.page} of {data.total_pages}{" "}
+        {data.page < data.total_pages && <a href={href({ page: data.page + 1 })}>Next</a>}
       </div>
     </div>
   )
@@ -16,7 +16,8 @@ export default function Create() {
       <form action="/admin/posts" method="post" style={{ display: 'flex', flexDirection: 'column', alignItems: 'start' }}>
         <input type="hidden" name="post_type" value={data.post_type.id} />
         <input type="text" name="title" placeholder="Title" />
-        <textarea name="content" placeholder="Content"></textarea>
+        <textarea name="content" placeholder="Content (Markdown)"></textarea>
+        <textarea name="blocks" placeholder='Blocks (optional JSON, e.g. [{"type": "heading", "text": "Hello"}])'></textarea>
 
         {data.errors.map(error => (
           <ul>
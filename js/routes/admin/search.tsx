@@ -0,0 +1,28 @@
+import { SearchResults, useRouteData } from '$lib'
+import React from 'react'
+
+export default function Search() {
+  const data = useRouteData<SearchResults>();
+
+  return (
+    <div>
+      <h1>Search</h1>
+
+      <form action="/admin/search" method="get">
+        <input type="search" name="q" placeholder="Search all content" defaultValue={data.query} />
+        <button type="submit">Search</button>
+      </form>
+
+      {data.query && data.results.length === 0 && <p>No results for "{data.query}"</p>}
+
+      <ol>
+        {data.results.map(result => (
+          <li key={result.id}>
+            <a href={result.url}>{result.title}</a> ({result.status})
+            {result.snippet && <p dangerouslySetInnerHTML={{ __html: result.snippet }} />}
+          </li>
+        ))}
+      </ol>
+    </div>
+  )
+}
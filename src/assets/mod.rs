@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+use anyhow::Error;
+use sha2::{Digest, Sha256};
+
+/// URL prefix every asset is served under.
+pub const ASSETS_PREFIX: &str = "/assets/";
+
+const HASH_LENGTH: usize = 8;
+
+static MANIFEST: OnceLock<AssetManifest> = OnceLock::new();
+/// CSS collected from the imports of rendered routes, by fingerprinted name.
+static STYLESHEETS: OnceLock<RwLock<HashMap<String, String>>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct Asset {
+    /// file on disk
+    pub path: PathBuf,
+    /// fingerprinted name, e.g. `css/site.3f2a1c4b.css`
+    pub hashed_name: String,
+    pub etag: String,
+    pub mime_type: String,
+}
+
+impl Asset {
+    pub fn url(&self) -> String {
+        format!("{}{}", ASSETS_PREFIX, self.hashed_name)
+    }
+}
+
+/// Every file in the public directory, fingerprinted by content at startup so
+/// its URL changes whenever the file does.
+#[derive(Debug, Default)]
+pub struct AssetManifest {
+    by_name: HashMap<String, Asset>,
+    by_hashed_name: HashMap<String, String>,
+}
+
+impl AssetManifest {
+    pub fn build(dir: &Path) -> Result<Self, Error> {
+        let mut manifest = Self::default();
+        if dir.is_dir() {
+            manifest.add_dir(dir, dir)?;
+        }
+
+        Ok(manifest)
+    }
+
+    fn add_dir(&mut self, root: &Path, dir: &Path) -> Result<(), Error> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.add_dir(root, &path)?;
+                continue;
+            }
+
+            let name = path
+                .strip_prefix(root)?
+                .components()
+                .map(|it| it.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let hash = fingerprint(&std::fs::read(&path)?);
+            let hash = hash.as_str();
+
+            let hashed_name = match name.rsplit_once('.') {
+                Some((stem, extension)) if !stem.ends_with('/') => {
+                    format!("{}.{}.{}", stem, hash, extension)
+                }
+                _ => format!("{}.{}", name, hash),
+            };
+
+            let asset = Asset {
+                mime_type: mime_guess::from_path(&path)
+                    .first_or_octet_stream()
+                    .to_string(),
+                path,
+                hashed_name: hashed_name.clone(),
+                etag: format!("\"{}\"", hash),
+            };
+
+            self.by_hashed_name.insert(hashed_name, name.clone());
+            self.by_name.insert(name, asset);
+        }
+
+        Ok(())
+    }
+
+    /// Look up an asset by its fingerprinted name, or by its plain name.
+    /// The flag is true for fingerprinted names, which can be cached forever.
+    pub fn resolve(&self, name: &str) -> Option<(&Asset, bool)> {
+        if let Some(asset) = self
+            .by_hashed_name
+            .get(name)
+            .and_then(|it| self.by_name.get(it))
+        {
+            return Some((asset, true));
+        }
+
+        self.by_name.get(name).map(|it| (it, false))
+    }
+}
+
+fn fingerprint(bytes: &[u8]) -> String {
+    let hash = format!("{:x}", Sha256::digest(bytes));
+    hash[..HASH_LENGTH].to_string()
+}
+
+/// Build the manifest for `dir`, making it available through [`manifest`].
+pub fn init(dir: &Path) -> Result<&'static AssetManifest, Error> {
+    let manifest = AssetManifest::build(dir)?;
+    tracing::debug!("Fingerprinted {} assets", manifest.by_name.len());
+
+    Ok(MANIFEST.get_or_init(|| manifest))
+}
+
+/// The manifest built by [`init`], empty before that.
+pub fn manifest() -> &'static AssetManifest {
+    MANIFEST.get_or_init(AssetManifest::default)
+}
+
+fn stylesheets() -> &'static RwLock<HashMap<String, String>> {
+    STYLESHEETS.get_or_init(Default::default)
+}
+
+/// Make the CSS of a rendered route available under a fingerprinted URL, which is returned.
+pub fn register_stylesheet(css: &str) -> String {
+    let name = format!("styles/route.{}.css", fingerprint(css.as_bytes()));
+    stylesheets()
+        .write()
+        .unwrap()
+        .entry(name.clone())
+        .or_insert_with(|| css.to_string());
+
+    format!("{}{}", ASSETS_PREFIX, name)
+}
+
+/// A stylesheet registered through [`register_stylesheet`], with its etag.
+pub fn stylesheet(name: &str) -> Option<(String, String)> {
+    let css = stylesheets().read().unwrap().get(name)?.clone();
+    let hash = name.trim_end_matches(".css").rsplit('.').next()?;
+
+    Some((format!("\"{}\"", hash), css))
+}
@@ -0,0 +1,228 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A single piece of structured content, e.g. `{ "type": "heading", "text": "Hi", "level": 2 }`.
+///
+/// Every key besides `type` is a field of the block, checked against the
+/// matching [`BlockDefinition`] before it is saved.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Block {
+    #[serde(rename = "type")]
+    pub block_type: String,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+/// Hosts an `embed` block may frame. Embeds get `allow-same-origin`, so a page
+/// of this site must never be one of them, it could lift its own sandbox.
+const EMBED_HOSTS: &[&str] = &[
+    "www.youtube.com",
+    "www.youtube-nocookie.com",
+    "player.vimeo.com",
+    "open.spotify.com",
+    "codepen.io",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Number,
+    /// absolute `http(s)` URL or a path on this site
+    Url,
+    /// `https` URL on one of [`EMBED_HOSTS`]
+    EmbedUrl,
+    Object,
+}
+
+impl FieldKind {
+    fn accepts(&self, value: &Value) -> bool {
+        match self {
+            FieldKind::Text => value.is_string(),
+            FieldKind::Number => value.is_number(),
+            FieldKind::Url => value.as_str().is_some_and(|it| {
+                it.starts_with("https://")
+                    || it.starts_with("http://")
+                    || (it.starts_with('/') && !it.starts_with("//"))
+            }),
+            FieldKind::EmbedUrl => value.as_str().is_some_and(is_embeddable),
+            FieldKind::Object => value.is_object(),
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            FieldKind::Text => "a string",
+            FieldKind::Number => "a number",
+            FieldKind::Url => "an http(s) URL or an absolute path",
+            FieldKind::EmbedUrl => "an https URL of a site that can be embedded",
+            FieldKind::Object => "an object",
+        }
+    }
+}
+
+fn is_embeddable(url: &str) -> bool {
+    let authority = match url.strip_prefix("https://") {
+        Some(rest) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => return false,
+    };
+    // `https://www.youtube.com@evil.example/` is a URL of evil.example
+    if authority.contains('@') {
+        return false;
+    }
+    let host = authority.split(':').next().unwrap_or_default();
+
+    EMBED_HOSTS.iter().any(|it| it.eq_ignore_ascii_case(host))
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldDefinition {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockDefinition {
+    pub name: &'static str,
+    pub fields: Vec<FieldDefinition>,
+}
+
+impl BlockDefinition {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            fields: vec![],
+        }
+    }
+
+    pub fn required(mut self, name: &'static str, kind: FieldKind) -> Self {
+        self.fields.push(FieldDefinition {
+            name,
+            kind,
+            required: true,
+        });
+        self
+    }
+
+    pub fn optional(mut self, name: &'static str, kind: FieldKind) -> Self {
+        self.fields.push(FieldDefinition {
+            name,
+            kind,
+            required: false,
+        });
+        self
+    }
+}
+
+/// The block types a post may contain, mirrored by the `<Blocks>` renderer in `js/lib.tsx`.
+#[derive(Clone)]
+pub struct BlockRegistry {
+    definitions: Arc<HashMap<&'static str, BlockDefinition>>,
+    /// names accepted by the `component` block, see `customComponents` in `js/lib.tsx`
+    components: Arc<HashSet<&'static str>>,
+}
+
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        Self::new(vec![
+            BlockDefinition::new("heading")
+                .required("text", FieldKind::Text)
+                .optional("level", FieldKind::Number),
+            BlockDefinition::new("paragraph").required("text", FieldKind::Text),
+            BlockDefinition::new("image")
+                .required("src", FieldKind::Url)
+                .optional("alt", FieldKind::Text)
+                .optional("caption", FieldKind::Text),
+            BlockDefinition::new("quote")
+                .required("text", FieldKind::Text)
+                .optional("cite", FieldKind::Text),
+            BlockDefinition::new("embed")
+                .required("url", FieldKind::EmbedUrl)
+                .optional("title", FieldKind::Text),
+            BlockDefinition::new("call_to_action")
+                .required("label", FieldKind::Text)
+                .required("href", FieldKind::Url)
+                .optional("text", FieldKind::Text),
+            BlockDefinition::new("component")
+                .required("name", FieldKind::Text)
+                .optional("props", FieldKind::Object),
+        ])
+    }
+}
+
+impl BlockRegistry {
+    pub fn new(definitions: Vec<BlockDefinition>) -> Self {
+        Self {
+            definitions: Arc::new(definitions.into_iter().map(|it| (it.name, it)).collect()),
+            components: Arc::new(HashSet::new()),
+        }
+    }
+
+    /// Allow a React component from `js/components/` to be used in a `component` block.
+    pub fn with_component(mut self, name: &'static str) -> Self {
+        Arc::make_mut(&mut self.components).insert(name);
+        self
+    }
+
+    /// Parse a JSON array of blocks, returning every problem found in it.
+    pub fn parse(&self, json: &str) -> Result<Vec<Block>, Vec<String>> {
+        let blocks = serde_json::from_str::<Vec<Block>>(json)
+            .map_err(|err| vec![format!("Blocks are not valid JSON: {}", err)])?;
+
+        let errors = blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(index, block)| self.validate(index, block))
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(blocks)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate(&self, index: usize, block: &Block) -> Vec<String> {
+        let prefix = format!("Block {} ({})", index + 1, block.block_type);
+
+        let definition = match self.definitions.get(block.block_type.as_str()) {
+            Some(definition) => definition,
+            None => return vec![format!("{}: unknown block type", prefix)],
+        };
+
+        let mut errors = vec![];
+        for field in &definition.fields {
+            match block.fields.get(field.name) {
+                Some(value) if !field.kind.accepts(value) => errors.push(format!(
+                    "{}: `{}` must be {}",
+                    prefix,
+                    field.name,
+                    field.kind.describe()
+                )),
+                None if field.required => {
+                    errors.push(format!("{}: `{}` is required", prefix, field.name))
+                }
+                _ => {}
+            }
+        }
+
+        for name in block.fields.keys() {
+            if !definition.fields.iter().any(|it| it.name == name) {
+                errors.push(format!("{}: unknown field `{}`", prefix, name));
+            }
+        }
+
+        if block.block_type == "component" {
+            if let Some(name) = block.fields.get("name").and_then(|it| it.as_str()) {
+                if !self.components.contains(name) {
+                    errors.push(format!("{}: unknown component `{}`", prefix, name));
+                }
+            }
+        }
+
+        errors
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Environment {
+    /// routes are compiled from `js/` on every request
+    Development,
+    /// routes are served from the bundles written by `experimental-cms build`
+    Production,
+}
+
+static ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
+
+/// Set the environment the server runs in, see [`Environment::current`].
+pub fn init(config: &Config) {
+    ENVIRONMENT.get_or_init(|| config.environment);
+}
+
+impl Environment {
+    /// The environment passed to [`init`], production before that so nothing
+    /// meant for developers leaks.
+    pub fn current() -> Self {
+        ENVIRONMENT
+            .get()
+            .copied()
+            .unwrap_or(Environment::Production)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Development => "development",
+            Environment::Production => "production",
+        }
+    }
+}
+
+/// Settings that differ between environments, read from environment variables.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// `CMS_ENV`, `development` unless set to `production`
+    pub environment: Environment,
+    /// where `experimental-cms build` writes bundles and production reads them, `BUNDLE_DIR`
+    pub bundle_dir: PathBuf,
+    /// minify bundles, `MINIFY`, on in production unless set to `false`
+    pub minify: bool,
+    /// where uploaded media is stored, `MEDIA_DIR`
+    pub media_dir: PathBuf,
+    /// where resized images are cached, `IMAGE_CACHE_DIR`
+    pub image_cache_dir: PathBuf,
+    /// static files served under `/assets/`, `PUBLIC_DIR`
+    pub public_dir: PathBuf,
+    /// where the bundle of every rendered route is dumped, `DEBUG_BUNDLE_DIR`
+    /// (e.g. `debug-bundles`), off when unset
+    pub debug_bundle_dir: Option<PathBuf>,
+    /// name of the site, shown in the title of every page, `SITE_NAME`
+    pub site_name: String,
+    /// public URL canonical URLs are built from, `SITE_URL`
+    pub site_url: String,
+    /// language of the pages, `SITE_LANG`, the locale unless set
+    pub site_lang: String,
+    /// locale of the messages templates get through `cms.t`, `CMS_LOCALE`
+    pub locale: String,
+    /// where `<locale>.json` is read from, `TRANSLATIONS_DIR`
+    pub translations_dir: PathBuf,
+    /// how long rendering a route may take, `RENDER_TIMEOUT_MS`, 2 seconds in
+    /// production and 10 while developing
+    pub render_timeout: Duration,
+    /// memory a single render may use, `RENDER_MEMORY_LIMIT_MB`, 64 MiB by default
+    pub render_memory_limit: usize,
+    /// JavaScript stack a single render may use, `RENDER_STACK_SIZE_KB`, 512 KiB by default
+    pub render_stack_size: usize,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let environment = match env_or("CMS_ENV", "development").as_str() {
+            "production" => Environment::Production,
+            _ => Environment::Development,
+        };
+
+        let minify = match std::env::var("MINIFY") {
+            Ok(minify) => minify == "true",
+            Err(_) => environment == Environment::Production,
+        };
+
+        let render_timeout = match environment {
+            Environment::Production => "2000",
+            Environment::Development => "10000",
+        };
+
+        let locale = env_or("CMS_LOCALE", "en");
+        let site_lang = env_or("SITE_LANG", &locale);
+        // sent as `Content-Language` with every page
+        if HeaderValue::from_str(&site_lang).is_err() {
+            panic!("SITE_LANG has to be a language tag like en or de-CH");
+        }
+
+        Self {
+            environment,
+            bundle_dir: env_or("BUNDLE_DIR", "dist").into(),
+            minify,
+            media_dir: env_or("MEDIA_DIR", "uploads").into(),
+            image_cache_dir: env_or("IMAGE_CACHE_DIR", "image-cache").into(),
+            public_dir: env_or("PUBLIC_DIR", "public").into(),
+            debug_bundle_dir: std::env::var("DEBUG_BUNDLE_DIR").ok().map(PathBuf::from),
+            site_name: env_or("SITE_NAME", "Hello World"),
+            site_url: env_or("SITE_URL", "http://localhost:3000"),
+            site_lang,
+            locale,
+            translations_dir: env_or("TRANSLATIONS_DIR", "translations").into(),
+            render_timeout: Duration::from_millis(env_number("RENDER_TIMEOUT_MS", render_timeout)),
+            render_memory_limit: env_number::<usize>("RENDER_MEMORY_LIMIT_MB", "64") << 20,
+            render_stack_size: env_number::<usize>("RENDER_STACK_SIZE_KB", "512") << 10,
+        }
+    }
+}
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.into())
+}
+
+fn env_number<T: std::str::FromStr>(name: &str, default: &str) -> T {
+    env_or(name, default)
+        .parse()
+        .unwrap_or_else(|_| panic!("{} has to be a number", name))
+}
@@ -1,11 +1,27 @@
+use std::collections::BTreeMap;
+
 use surrealdb::{sql::Value, Datastore, Error, Session};
 use tokio::sync::{mpsc, oneshot};
 
 pub mod models;
 
+/// Parameters bound to `$name` placeholders in a statement, so user input
+/// never has to be formatted into the query text.
+pub type Vars = BTreeMap<String, Value>;
+
+/// `$type` for `type::thing('postType', $type)`, from the id of a post type
+/// with or without the `postType:` prefix.
+pub fn post_type_key(id: &str) -> Value {
+    id.strip_prefix("postType:")
+        .unwrap_or(id)
+        .to_string()
+        .into()
+}
+
 type QueryResult = (
     oneshot::Sender<Result<Vec<surrealdb::Response>, Error>>,
     String,
+    Option<Vars>,
 );
 
 #[derive(Clone)]
@@ -22,8 +38,8 @@ impl Db {
             let session = Session::for_db(namespace.to_string(), database.to_string());
 
             while let Some(statement) = stmt_rx.recv().await {
-                let (tx, query) = statement;
-                let responses = datastore.execute(&query, &session, None, false).await;
+                let (tx, query, vars) = statement;
+                let responses = datastore.execute(&query, &session, vars, false).await;
                 tx.send(responses).unwrap();
             }
         });
@@ -33,15 +49,27 @@ impl Db {
         }
     }
 
-    pub async fn query(&self, statement: &str) -> Result<Vec<Value>, Error> {
+    async fn execute(
+        &self,
+        statement: &str,
+        vars: Option<Vars>,
+    ) -> Result<Vec<surrealdb::Response>, Error> {
         let (tx, rx) = oneshot::channel();
 
         self.query_sender
-            .send((tx, statement.to_string()))
+            .send((tx, statement.to_string(), vars))
             .await
             .unwrap();
 
-        let responses = rx.await.unwrap()?;
+        rx.await.unwrap()
+    }
+
+    pub async fn query(&self, statement: &str) -> Result<Vec<Value>, Error> {
+        self.query_with_vars(statement, Vars::new()).await
+    }
+
+    pub async fn query_with_vars(&self, statement: &str, vars: Vars) -> Result<Vec<Value>, Error> {
+        let responses = self.execute(statement, Some(vars)).await?;
         let mut results = Vec::new();
 
         for response in responses {
@@ -57,14 +85,15 @@ impl Db {
         &self,
         statement: &str,
     ) -> Result<T, Error> {
-        let (tx, rx) = oneshot::channel();
-
-        self.query_sender
-            .send((tx, statement.to_string()))
-            .await
-            .unwrap();
+        self.query_first_with_vars(statement, Vars::new()).await
+    }
 
-        let responses = rx.await.unwrap()?;
+    pub async fn query_first_with_vars<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+        statement: &str,
+        vars: Vars,
+    ) -> Result<T, Error> {
+        let responses = self.execute(statement, Some(vars)).await?;
         let response = responses.first().expect("Query returned nothing");
         let result = response.result.as_ref().unwrap();
 
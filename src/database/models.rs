@@ -1,11 +1,24 @@
 use serde::{Deserialize, Serialize};
 
+use crate::blocks::Block;
+use crate::markdown::{self, RenderedMarkdown};
+use crate::sanitize::SanitizePolicy;
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct PostType {
     pub id: String,
     pub singular: String,
     pub plural: String,
     pub path_prefix: Option<String>,
+    /// HTML allowed in the content of this type, `SanitizePolicy::default()` when unset
+    pub sanitize: Option<SanitizePolicy>,
+}
+
+impl PostType {
+    /// Public URL of a post of this type, mirroring how `page` resolves slugs.
+    pub fn url_for(&self, slug: &str) -> String {
+        format!("{}{}", self.path_prefix.as_deref().unwrap_or("/"), slug)
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -15,11 +28,47 @@ pub struct Post {
     pub content: String,
     pub slug: String,
     pub status: String,
+    pub created_at: Option<String>,
+    /// structured alternative to `content`, rendered by `<Blocks>` when present
+    pub blocks: Option<Vec<Block>>,
+    /// `content` rendered and sanitized when the post was saved
+    pub rendered: Option<RenderedMarkdown>,
 
     #[serde(rename = "type")]
     pub post_type: String,
 }
 
+impl Post {
+    /// The text of the post without any markup, from its paragraph and heading
+    /// blocks or its Markdown, and the URL of its first image.
+    pub fn summary(&self) -> (String, Option<String>) {
+        let blocks = match &self.blocks {
+            Some(blocks) => blocks,
+            None => return markdown::summary(&self.content),
+        };
+
+        let field = |block: &Block, name: &str| {
+            block
+                .fields
+                .get(name)
+                .and_then(|it| it.as_str())
+                .map(String::from)
+        };
+        let text = blocks
+            .iter()
+            .filter(|it| it.block_type == "paragraph" || it.block_type == "heading")
+            .filter_map(|it| field(it, "text"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let image = blocks
+            .iter()
+            .filter(|it| it.block_type == "image")
+            .find_map(|it| field(it, "src"));
+
+        (text, image)
+    }
+}
+
 impl From<PostWithPostType> for Post {
     fn from(post_with_post_type: PostWithPostType) -> Self {
         Post {
@@ -28,6 +77,9 @@ impl From<PostWithPostType> for Post {
             content: post_with_post_type.content,
             slug: post_with_post_type.slug,
             status: post_with_post_type.status,
+            created_at: post_with_post_type.created_at,
+            blocks: post_with_post_type.blocks,
+            rendered: post_with_post_type.rendered,
             post_type: post_with_post_type.post_type.id,
         }
     }
@@ -40,7 +92,63 @@ pub struct PostWithPostType {
     pub content: String,
     pub slug: String,
     pub status: String,
+    pub created_at: Option<String>,
+    /// structured alternative to `content`, rendered by `<Blocks>` when present
+    pub blocks: Option<Vec<Block>>,
+    /// `content` rendered and sanitized when the post was saved
+    pub rendered: Option<RenderedMarkdown>,
 
     #[serde(rename = "type")]
     pub post_type: PostType,
 }
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Media {
+    pub id: String,
+    /// original name of the uploaded file
+    pub filename: String,
+    /// name of the file in `Storage`, served at `/uploads/{key}`
+    pub key: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub created_at: Option<String>,
+}
+
+/// Field a list of posts is ordered by.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PostSort {
+    Title,
+    #[default]
+    CreatedAt,
+    Status,
+}
+
+impl PostSort {
+    pub fn field(&self) -> &'static str {
+        match self {
+            PostSort::Title => "title",
+            PostSort::CreatedAt => "created_at",
+            PostSort::Status => "status",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, TimeZone, Utc};
+use either::Either;
+use rquickjs::{Coerced, Ctx, Func, Object, Opt};
+
+use crate::assets::{self, ASSETS_PREFIX};
+use crate::config::Config;
+use crate::database::models::PostType;
+use crate::i18n;
+
+/// Used by `cms.formatDate` without a format.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+static ENV: OnceLock<HostEnv> = OnceLock::new();
+/// only created by `setup_structure`, so they're loaded once for every render
+static POST_TYPES: OnceLock<Vec<PostType>> = OnceLock::new();
+
+/// What templates get to know about the server through `cms.env`.
+#[derive(Debug, Clone)]
+pub struct HostEnv {
+    pub environment: &'static str,
+    pub locale: String,
+    pub version: &'static str,
+}
+
+/// Set the values of `cms.env` and the post types `cms.url` links to.
+pub fn init(config: &Config, post_types: Vec<PostType>) {
+    ENV.get_or_init(|| HostEnv {
+        environment: config.environment.as_str(),
+        locale: config.locale.clone(),
+        version: env!("CARGO_PKG_VERSION"),
+    });
+    POST_TYPES.get_or_init(|| post_types);
+}
+
+/// Define `cms`, the global of templates, on the global object of `ctx`, see `js/cms.d.ts`.
+pub fn register(ctx: Ctx) -> rquickjs::Result<()> {
+    let cms = Object::new(ctx)?;
+
+    cms.set("url", Func::new("url", url))?;
+    cms.set("formatDate", Func::new("formatDate", format_date))?;
+    cms.set("asset", Func::new("asset", asset))?;
+    cms.set("t", Func::new("t", translate))?;
+
+    let env = ENV.get();
+    let env_object = Object::new(ctx)?;
+    env_object.set(
+        "environment",
+        env.map_or("development", |it| it.environment),
+    )?;
+    env_object.set("locale", env.map_or("en", |it| it.locale.as_str()))?;
+    env_object.set("version", env.map_or("", |it| it.version))?;
+    cms.set("env", env_object)?;
+
+    ctx.globals().set("cms", cms)
+}
+
+/// Public URL of a post from its `slug` and `type`, `undefined` when there's no
+/// such post type.
+///
+/// Templates pass posts they've already loaded, so rendering never waits for the database.
+fn url(post: Object) -> rquickjs::Result<Option<String>> {
+    let slug = post.get::<_, String>("slug")?;
+    let post_type = post.get::<_, String>("type")?;
+
+    Ok(POST_TYPES
+        .get()
+        .and_then(|post_types| post_types.iter().find(|it| it.id == post_type))
+        .map(|it| it.url_for(&slug)))
+}
+
+/// Format a `Date`, an ISO 8601 string (as stored by the database) or
+/// milliseconds since the epoch with a strftime format, e.g. `%d %B %Y`.
+fn format_date(
+    date: Either<DateTime<Utc>, Either<String, f64>>,
+    format: Opt<String>,
+) -> rquickjs::Result<String> {
+    let date = match date {
+        Either::Left(date) => date,
+        Either::Right(Either::Left(iso)) => DateTime::parse_from_rfc3339(&iso)
+            .map_err(|err| rquickjs::Error::new_from_js_message("string", "Date", err.to_string()))?
+            .with_timezone(&Utc),
+        Either::Right(Either::Right(millis)) => Utc
+            .timestamp_millis_opt(millis as i64)
+            .single()
+            .ok_or_else(|| {
+                rquickjs::Error::new_from_js_message("number", "Date", "timestamp out of range")
+            })?,
+    };
+
+    let format = format.0.unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_string());
+    // chrono panics when displaying an invalid format
+    if StrftimeItems::new(&format).any(|it| it == Item::Error) {
+        return Err(rquickjs::Error::new_from_js_message(
+            "string",
+            "date format",
+            format!("invalid format {}", format),
+        ));
+    }
+
+    Ok(date.format(&format).to_string())
+}
+
+/// Fingerprinted URL of a file in the public directory.
+fn asset(path: String) -> String {
+    match assets::manifest().resolve(&path) {
+        Some((asset, _)) => asset.url(),
+        None => format!("{}{}", ASSETS_PREFIX, path),
+    }
+}
+
+fn translate(key: String, vars: Opt<HashMap<String, Coerced<String>>>) -> String {
+    let vars = vars
+        .0
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| (name, value.0))
+        .collect();
+
+    i18n::translations().translate(&key, &vars)
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Error};
+use serde_json::Value;
+
+static TRANSLATIONS: OnceLock<Translations> = OnceLock::new();
+
+/// Messages for a single locale, read from `<dir>/<locale>.json`.
+///
+/// Nested objects are flattened into dotted keys, so `{"nav": {"home": "Home"}}`
+/// is looked up as `nav.home`.
+#[derive(Debug, Default)]
+pub struct Translations {
+    pub locale: String,
+    messages: HashMap<String, String>,
+}
+
+impl Translations {
+    pub fn load(dir: &Path, locale: &str) -> Result<Self, Error> {
+        let path = dir.join(format!("{}.json", locale));
+        let mut translations = Self {
+            locale: locale.to_string(),
+            messages: HashMap::new(),
+        };
+        if !path.is_file() {
+            tracing::warn!("No translations for {} at {}", locale, path.display());
+            return Ok(translations);
+        }
+
+        let value: Value = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        flatten("", &value, &mut translations.messages);
+
+        Ok(translations)
+    }
+
+    /// The message for `key` with every `{name}` in it replaced by `vars[name]`,
+    /// or the key itself when there's no such message.
+    pub fn translate(&self, key: &str, vars: &HashMap<String, String>) -> String {
+        let message = match self.messages.get(key) {
+            Some(message) => message,
+            None => {
+                tracing::warn!("Missing translation of {} for {}", key, self.locale);
+                return key.to_string();
+            }
+        };
+
+        vars.iter().fold(message.clone(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), value)
+        })
+    }
+}
+
+fn flatten(prefix: &str, value: &Value, messages: &mut HashMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, messages);
+            }
+        }
+        Value::String(message) => {
+            messages.insert(prefix.to_string(), message.clone());
+        }
+        value => {
+            messages.insert(prefix.to_string(), value.to_string());
+        }
+    }
+}
+
+/// Use `translations` from now on, see [`translations`].
+pub fn init(translations: Translations) -> &'static Translations {
+    tracing::debug!(
+        "Loaded {} translations for {}",
+        translations.messages.len(),
+        translations.locale
+    );
+    TRANSLATIONS.get_or_init(|| translations)
+}
+
+/// The translations passed to [`init`], none before that.
+pub fn translations() -> &'static Translations {
+    TRANSLATIONS.get_or_init(Translations::default)
+}
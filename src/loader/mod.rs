@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use surrealdb::Error;
+
+use crate::database::models::{Media, Post, PostSort, PostType, SortOrder};
+use crate::database::{post_type_key, Db, Vars};
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+
+/// Data a route asks for by exporting `loader` from its TSX file, e.g.
+///
+/// ```ts
+/// export const loader: Loader = {
+///   latestPosts: { query: 'posts', limit: 5 },
+///   author: { query: 'post', slug: { param: 'slug' } },
+/// }
+/// ```
+///
+/// It's read from the source when the route is bundled, so it has to be a
+/// literal. Every result ends up in the route data under its name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Loader {
+    pub queries: BTreeMap<String, LoaderQuery>,
+}
+
+/// The queries a loader may run, all of them read-only and limited to
+/// published posts.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "query", rename_all = "camelCase", deny_unknown_fields)]
+pub enum LoaderQuery {
+    #[serde(rename_all = "camelCase")]
+    Posts {
+        /// id of a post type, with or without the `postType:` prefix
+        #[serde(rename = "type")]
+        post_type: Option<Argument>,
+        #[serde(default)]
+        sort: PostSort,
+        #[serde(default)]
+        order: SortOrder,
+        limit: Option<usize>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Post {
+        slug: Argument,
+        #[serde(rename = "type")]
+        post_type: Option<Argument>,
+    },
+    PostTypes,
+    Media {
+        limit: Option<usize>,
+    },
+}
+
+/// A fixed value, or `{ param: 'name' }` for a param of the route (see `PageData`).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Argument {
+    Param { param: String },
+    Value(String),
+}
+
+impl Argument {
+    fn resolve(&self, params: &Map<String, Value>) -> Value {
+        match self {
+            Argument::Value(value) => value.clone().into(),
+            Argument::Param { param } => params.get(param).cloned().unwrap_or(Value::Null),
+        }
+    }
+}
+
+impl Loader {
+    /// Parse the `loader` export of a route, rejecting queries that aren't allowed.
+    pub fn from_value(value: Value) -> Result<Self, String> {
+        serde_json::from_value(value).map_err(|err| format!("invalid loader: {}", err))
+    }
+
+    /// Run every query, `data` is the route data the handler produced.
+    pub async fn load(&self, db: &Db, data: &Value) -> Result<Map<String, Value>, String> {
+        let empty = Map::new();
+        let params = data
+            .get("params")
+            .and_then(|it| it.as_object())
+            .unwrap_or(&empty);
+
+        let mut results = Map::new();
+        for (name, query) in &self.queries {
+            let result = query
+                .run(db, params)
+                .await
+                .map_err(|err| format!("loader query {} failed: {}", name, err))?;
+            results.insert(name.clone(), result);
+        }
+
+        Ok(results)
+    }
+}
+
+impl LoaderQuery {
+    async fn run(&self, db: &Db, params: &Map<String, Value>) -> Result<Value, Error> {
+        Ok(match self {
+            LoaderQuery::Posts {
+                post_type,
+                sort,
+                order,
+                limit,
+            } => {
+                let mut vars = Vars::new();
+                let type_condition = type_condition(post_type.as_ref(), params, &mut vars);
+
+                let posts = db
+                    .query_first_with_vars::<Vec<Post>>(
+                        &format!(
+                            "SELECT * FROM post WHERE status = 'published'{} ORDER BY {} {} LIMIT {}",
+                            type_condition,
+                            sort.field(),
+                            order.keyword(),
+                            clamp_limit(*limit)
+                        ),
+                        vars,
+                    )
+                    .await?;
+
+                serde_json::to_value(posts).unwrap()
+            }
+            LoaderQuery::Post { slug, post_type } => {
+                let mut vars = Vars::new();
+                let type_condition = type_condition(post_type.as_ref(), params, &mut vars);
+                vars.insert("slug".into(), string_var(slug.resolve(params)));
+
+                let posts = db
+                    .query_first_with_vars::<Vec<Post>>(
+                        &format!(
+                            "SELECT * FROM post WHERE status = 'published' AND slug = $slug{} LIMIT 1",
+                            type_condition
+                        ),
+                        vars,
+                    )
+                    .await?;
+
+                serde_json::to_value(posts.first()).unwrap()
+            }
+            LoaderQuery::PostTypes => {
+                let post_types = db
+                    .query_first::<Vec<PostType>>("SELECT * FROM postType")
+                    .await?;
+
+                serde_json::to_value(post_types).unwrap()
+            }
+            LoaderQuery::Media { limit } => {
+                let media = db
+                    .query_first::<Vec<Media>>(&format!(
+                        "SELECT * FROM media ORDER BY created_at DESC LIMIT {}",
+                        clamp_limit(*limit)
+                    ))
+                    .await?;
+
+                serde_json::to_value(media).unwrap()
+            }
+        })
+    }
+}
+
+/// ` AND type = ...` when a post type is given, binding it as `$type`.
+fn type_condition(
+    post_type: Option<&Argument>,
+    params: &Map<String, Value>,
+    vars: &mut Vars,
+) -> &'static str {
+    let post_type = match post_type {
+        Some(post_type) => post_type.resolve(params),
+        None => return "",
+    };
+
+    vars.insert(
+        "type".into(),
+        post_type_key(post_type.as_str().unwrap_or_default()),
+    );
+
+    " AND type = type::thing('postType', $type)"
+}
+
+/// A param that isn't a string, or missing, matches nothing.
+fn string_var(value: Value) -> surrealdb::sql::Value {
+    match value {
+        Value::String(value) => value.into(),
+        _ => surrealdb::sql::Value::None,
+    }
+}
+
+fn clamp_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
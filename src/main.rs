@@ -1,23 +1,48 @@
 use std::net::SocketAddr;
 use std::path::Path;
+use std::sync::Arc;
 
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
+use axum::middleware;
 use axum::response::IntoResponse;
-use axum::{response::Html, routing::get};
+use axum::routing::get;
 use axum::{Extension, Router};
-use database::Db;
+use blocks::BlockRegistry;
+use config::{Config, Environment};
+use database::{post_type_key, Db, Vars};
+use loader::Loader;
+use media::{LocalStorage, SharedStorage};
+use meta::PageMeta;
+use pages::PageRoutes;
+use search::SearchIndex;
 
 use rquickjs::{Func, Object};
+use tower_http::catch_panic::CatchPanicLayer;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tsx::compile_app;
+use tsx::bundles::PrebuiltBundles;
+use tsx::limits::{LimitedRuntime, RenderLimits};
+use tsx::page::HtmlPage;
+use tsx::{compile_app, CompileOptions, CompiledApp, RenderError};
 
 use crate::database::models::{Post, PostType};
 
+mod assets;
+mod blocks;
+mod config;
 mod database;
+mod host;
+mod i18n;
+mod loader;
+mod markdown;
+mod media;
+mod meta;
+mod pages;
 mod routers;
+mod sanitize;
+mod search;
 mod tsx;
 
 #[tokio::main]
@@ -25,18 +50,78 @@ async fn main() {
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
             std::env::var("RUST_LOG")
-                .unwrap_or_else(|_| "experimental-cms=debug,tower_http=debug".into()),
+                .unwrap_or_else(|_| "experimental_cms=debug,tower_http=debug".into()),
         ))
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    let config = Config::from_env();
+    config::init(&config);
+    tsx::import_map::init(&std::env::current_dir().unwrap()).expect("failed to load import map");
+    if std::env::args().nth(1).as_deref() == Some("build") {
+        build(&config);
+        return;
+    }
+
+    assets::init(&config.public_dir).unwrap();
+    tsx::debug::init(config.debug_bundle_dir.clone());
+    i18n::init(
+        i18n::Translations::load(&config.translations_dir, &config.locale)
+            .expect("failed to load translations"),
+    );
+    meta::init(&config);
+    tsx::init(CompileOptions {
+        minify: config.minify,
+    });
+    tsx::limits::init(RenderLimits {
+        timeout: config.render_timeout,
+        memory_limit: config.render_memory_limit,
+        max_stack_size: config.render_stack_size,
+    });
+    if config.environment == Environment::Production {
+        tsx::bundles::init(
+            PrebuiltBundles::load(&config.bundle_dir, TEMPLATES)
+                .expect("failed to load prebuilt bundles"),
+        );
+        // prebuilt bundles don't change, so neither do the pages among them
+        pages::init(PageRoutes::discover(TEMPLATES));
+    }
+
     let db = Db::new("test".into(), "test".into(), "file://temp.db".into()).await;
     database::setup_structure(&db).await;
+    host::init(
+        &config,
+        db.query_first::<Vec<PostType>>("SELECT * FROM postType")
+            .await
+            .unwrap(),
+    );
+
+    let search = SearchIndex::new().unwrap();
+    search.rebuild(&db).await.unwrap();
+
+    let storage: SharedStorage = Arc::new(LocalStorage::new(config.media_dir.clone()).unwrap());
 
     let app = Router::new()
         .nest("/admin", routers::admin::router())
+        .merge(routers::search::router())
+        .merge(routers::media::router())
+        .merge(routers::assets::router())
         .fallback(get(page))
+        // panics of handlers get an error page, panics while rendering it a bare 500
+        .layer(CatchPanicLayer::custom(
+            routers::error_pages::panic_response,
+        ))
+        .layer(middleware::from_fn(
+            routers::error_pages::render_error_pages,
+        ))
+        .layer(CatchPanicLayer::custom(
+            routers::error_pages::panic_response,
+        ))
         .layer(Extension(db))
+        .layer(Extension(storage))
+        .layer(Extension(search))
+        .layer(Extension(BlockRegistry::default().with_component("Test")))
+        .layer(Extension(config))
         .layer(axum_flash::layer(axum_flash::Key::generate()).with_cookie_manager())
         .layer(TraceLayer::new_for_http());
 
@@ -48,27 +133,153 @@ async fn main() {
         .unwrap();
 }
 
+/// `experimental-cms build`: bundle every route for production.
+fn build(config: &Config) {
+    std::fs::create_dir_all(&config.bundle_dir).unwrap();
+    let options = CompileOptions {
+        minify: config.minify,
+    };
+    let manifest = tsx::bundles::build(&config.bundle_dir, options).unwrap();
+
+    for route in TEMPLATES {
+        if !manifest.routes.contains_key(*route) {
+            panic!("route {} is used by the server but doesn't exist", route);
+        }
+    }
+
+    // a line per event keeps the columns of the table aligned
+    for line in manifest.size_report().lines() {
+        tracing::info!("{}", line);
+    }
+    tracing::info!(
+        "Wrote {} bundles to {}",
+        manifest.routes.len(),
+        config.bundle_dir.display()
+    );
+}
+
+/// Every route passed to `template`, which has to be among the prebuilt bundles in production.
+const TEMPLATES: &[&str] = &[
+    "(site)/404.tsx",
+    "(site)/500.tsx",
+    "(site)/post.tsx",
+    "(site)/search.tsx",
+    "admin/index.tsx",
+    "admin/media.tsx",
+    "admin/posts.tsx",
+    "admin/posts/create.tsx",
+    "admin/search.tsx",
+];
+
 fn print(msg: String) {
     println!("{}", msg);
 }
 
-fn template(path: &Path, data: String) -> Html<String> {
-    let js = compile_app(path, data);
+/// Render the route at `path` (below `js/routes/`) with `data` as its route
+/// data, along with whatever its `loader` asks for.
+async fn template(db: &Db, path: &Path, data: serde_json::Value) -> Result<HtmlPage, RenderError> {
+    template_with_meta(db, path, data, PageMeta::site()).await
+}
+
+/// [`template`] with `meta` as the defaults of what goes in the `<head>`.
+async fn template_with_meta(
+    db: &Db,
+    path: &Path,
+    data: serde_json::Value,
+    meta: PageMeta,
+) -> Result<HtmlPage, RenderError> {
+    let app = match tsx::bundles::prebuilt() {
+        Some(bundles) => bundles
+            .get(path)
+            .unwrap_or_else(|| panic!("{} is not among the prebuilt bundles", path.display())),
+        None => Arc::new(compile_app(path, tsx::options())),
+    };
+    let data = match &app.loader {
+        Some(Ok(loader)) => with_loader_data(db, path, loader, data).await?,
+        Some(Err(err)) => return Err(RenderError::from_message(path, err.clone())),
+        None => data,
+    };
+
+    let html = render(path, &app, &data, &meta)?;
+    Ok(HtmlPage {
+        html,
+        lang: meta.lang,
+    })
+}
+
+/// Add the results of `loader` to the route data, the data of the handler wins
+/// when both have the same key.
+async fn with_loader_data(
+    db: &Db,
+    path: &Path,
+    loader: &Loader,
+    mut data: serde_json::Value,
+) -> Result<serde_json::Value, RenderError> {
+    let results = loader
+        .load(db, &data)
+        .await
+        .map_err(|err| RenderError::from_message(path, err))?;
+
+    let object = match data.as_object_mut() {
+        Some(object) => object,
+        None => {
+            return Err(RenderError::from_message(
+                path,
+                "a route with a loader needs an object as route data",
+            ))
+        }
+    };
+    for (name, value) in results {
+        if object.contains_key(&name) {
+            tracing::warn!(
+                "loader result {} of {} is shadowed by the route data",
+                name,
+                path.display()
+            );
+            continue;
+        }
+        object.insert(name, value);
+    }
+
+    Ok(data)
+}
+
+/// Evaluate the bundle of a route to the markup of its document.
+fn render(
+    path: &Path,
+    app: &CompiledApp,
+    data: &serde_json::Value,
+    meta: &PageMeta,
+) -> Result<String, RenderError> {
+    let stylesheets = if app.css.is_empty() {
+        vec![]
+    } else {
+        vec![assets::register_stylesheet(&app.css)]
+    };
 
-    std::fs::write("out.js", &js).unwrap();
+    tsx::debug::dump(path, app);
 
-    let runtime = rquickjs::Runtime::new().unwrap();
-    let ctx = rquickjs::Context::full(&runtime).unwrap();
+    let runtime = LimitedRuntime::new(tsx::limits::limits());
+    let ctx = rquickjs::Context::full(&runtime.runtime).unwrap();
     let result = ctx.with(|ctx| {
         let global = ctx.globals();
         let obj = Object::new(ctx).unwrap();
         obj.set("log", Func::new("print", print)).unwrap();
         global.set("console", obj).unwrap();
+        host::register(ctx)?;
+        global.set("routeStylesheets", stylesheets).unwrap();
 
-        ctx.eval::<String, String>(js).unwrap()
+        global.set("routeData", tsx::json::to_js(ctx, data)?)?;
+        let meta = serde_json::to_value(meta).unwrap();
+        global.set("routeMeta", tsx::json::to_js(ctx, &meta)?)?;
+
+        ctx.eval::<String, _>(app.js.as_str())
     });
 
-    Html(result)
+    result.map_err(|err| {
+        let limit = runtime.exceeded(&err);
+        RenderError::new(path, app, err).with_limit(limit)
+    })
 }
 
 async fn page(Extension(db): Extension<Db>, request: Request<Body>) -> impl IntoResponse {
@@ -79,6 +290,17 @@ async fn page(Extension(db): Extension<Db>, request: Request<Body>) -> impl Into
 
     let uri = request.uri().path().trim_end_matches('/');
 
+    // pages in `js/routes/` take precedence over posts
+    if let Some((route, data)) = pages::routes(TEMPLATES).find(request.uri().path()) {
+        return Ok(template_with_meta(
+            &db,
+            &route.file,
+            serde_json::to_value(&data).unwrap(),
+            PageMeta::for_path(request.uri().path()),
+        )
+        .await);
+    }
+
     let maybe_post_type = post_types.iter().find(|it| {
         if let Some(path_prefix) = &it.path_prefix {
             uri.starts_with(path_prefix)
@@ -94,21 +316,37 @@ async fn page(Extension(db): Extension<Db>, request: Request<Body>) -> impl Into
         post_type_id = &post_type.id;
     }
 
+    let mut vars = Vars::new();
+    // remove the prefix so it matches the slug
+    vars.insert("slug".into(), uri.replace(prefix, "").into());
+    vars.insert("type".into(), post_type_key(post_type_id));
     let maybe_post = db
-        .query_first::<Vec<Post>>(&format!(
-            "SELECT * FROM post WHERE slug = '{}' AND type = '{}' AND status = 'published'",
-            // remove the prefix so it matches the slug
-            uri.replace(prefix, ""),
-            post_type_id
-        ))
+        .query_first_with_vars::<Vec<Post>>(
+            "SELECT * FROM post WHERE slug = $slug AND type = type::thing('postType', $type) AND status = 'published'",
+            vars,
+        )
         .await
         .unwrap();
 
     if let Some(post) = maybe_post.first() {
-        Ok(template(
-            Path::new("post.tsx"),
-            serde_json::to_string(&post).unwrap(),
-        ))
+        let mut post = post.clone();
+        let post_type = post_types.iter().find(|it| it.id == post.post_type);
+        // posts saved before rendering happened on save
+        if post.rendered.is_none() {
+            let policy = post_type
+                .and_then(|it| it.sanitize.clone())
+                .unwrap_or_default();
+            post.rendered = Some(markdown::render(&post.content, &policy).0);
+        }
+
+        let meta = PageMeta::for_post(&post, post_type);
+        Ok(template_with_meta(
+            &db,
+            Path::new("(site)/post.tsx"),
+            serde_json::to_value(&post).unwrap(),
+            meta,
+        )
+        .await)
     } else {
         Err(StatusCode::NOT_FOUND)
     }
@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use slugify::slugify;
+
+use crate::sanitize::{SanitizePolicy, SanitizeReport};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TocEntry {
+    pub level: u32,
+    /// `id` attribute of the rendered heading, usable as `#fragment`
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RenderedMarkdown {
+    pub html: String,
+    pub toc: Vec<TocEntry>,
+}
+
+/// Render CommonMark (with GFM tables, footnotes and strikethrough) to HTML
+/// sanitized by `policy`, giving every heading an id and collecting them into
+/// a table of contents.
+pub fn render(source: &str, policy: &SanitizePolicy) -> (RenderedMarkdown, SanitizeReport) {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_STRIKETHROUGH;
+    let mut events: Vec<Event> = Parser::new_ext(source, options).collect();
+
+    let mut toc = Vec::new();
+    let mut used_ids = HashSet::new();
+
+    let mut i = 0;
+    while i < events.len() {
+        let level = match &events[i] {
+            Event::Start(Tag::Heading(level, _, _)) => *level,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let end = events[i..]
+            .iter()
+            .position(|it| matches!(it, Event::End(Tag::Heading(..))))
+            .map(|offset| i + offset)
+            .unwrap_or(events.len() - 1);
+
+        let title = events[i + 1..end]
+            .iter()
+            .filter_map(|it| match it {
+                Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect::<String>();
+        let id = unique_id(&mut used_ids, &title);
+
+        // pulldown-cmark can't attach owned ids to a heading, so emit its tags ourselves
+        events[i] = Event::Html(format!("<{} id=\"{}\">", level, id).into());
+        events[end] = Event::Html(format!("</{}>\n", level).into());
+
+        toc.push(TocEntry {
+            level: level as u32,
+            id,
+            title,
+        });
+        i = end + 1;
+    }
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, events.into_iter());
+
+    let sanitized = policy.clean(&unsafe_html);
+    (
+        RenderedMarkdown {
+            html: sanitized.html,
+            toc,
+        },
+        sanitized.report,
+    )
+}
+
+/// The text of `source` without any markup, and the URL of its first image,
+/// e.g. for a description of the post it belongs to.
+pub fn summary(source: &str) -> (String, Option<String>) {
+    let mut text = String::new();
+    let mut image = None;
+    // the alt text of an image isn't part of the text around it
+    let mut in_image = false;
+
+    for event in Parser::new(source) {
+        match event {
+            Event::Text(it) | Event::Code(it) if !in_image => text.push_str(&it),
+            Event::Start(Tag::Image(_, url, _)) => {
+                in_image = true;
+                image.get_or_insert_with(|| url.to_string());
+            }
+            Event::End(Tag::Image(..)) => in_image = false,
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    (text, image)
+}
+
+fn unique_id(used_ids: &mut HashSet<String>, title: &str) -> String {
+    let mut slug = slugify!(title);
+    if slug.is_empty() {
+        slug = "section".into();
+    }
+
+    // a heading like "Foo 2" may already have the id a second "Foo" would get
+    let mut id = slug.clone();
+    let mut count = 1;
+    while used_ids.contains(&id) {
+        count += 1;
+        id = format!("{}-{}", slug, count);
+    }
+
+    used_ids.insert(id.clone());
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_repeated_titles() {
+        let mut used_ids = HashSet::new();
+        let ids = ["Foo", "Foo", "Foo"].map(|it| unique_id(&mut used_ids, it));
+
+        assert_eq!(ids, ["foo", "foo-2", "foo-3"]);
+    }
+
+    #[test]
+    fn skips_ids_of_numbered_titles() {
+        let mut used_ids = HashSet::new();
+        let ids = ["Foo 2", "Foo", "Foo", "Foo"].map(|it| unique_id(&mut used_ids, it));
+
+        assert_eq!(ids, ["foo-2", "foo", "foo-3", "foo-4"]);
+    }
+
+    #[test]
+    fn gives_headings_unique_ids() {
+        let (rendered, _) = render("# Foo 2\n\n## Foo\n\n## Foo\n", &SanitizePolicy::default());
+
+        let ids = rendered
+            .toc
+            .iter()
+            .map(|it| it.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["foo-2", "foo", "foo-3"]);
+        assert!(rendered.html.contains("<h2 id=\"foo-3\">Foo</h2>"));
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{Cursor, ErrorKind};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Error};
+use async_trait::async_trait;
+use slugify::slugify;
+use tokio::io::AsyncWriteExt;
+
+pub mod resize;
+
+/// Where the bytes of uploaded media live, the `media` table only keeps their metadata.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Fails when something is already stored under `key`.
+    async fn put(&self, key: &str, bytes: &[u8]) -> Result<(), Error>;
+    /// `None` when nothing is stored under `key`
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error>;
+    async fn delete(&self, key: &str) -> Result<(), Error>;
+}
+
+pub type SharedStorage = Arc<dyn Storage>;
+
+/// Stores every file directly in a directory on the local disk.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Result<Self, Error> {
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, Error> {
+        // keys are generated by `storage_key`, anything else could escape the root
+        if key.is_empty() || key.starts_with('.') || key.contains(['/', '\\']) {
+            bail!("invalid storage key: {}", key);
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, bytes: &[u8]) -> Result<(), Error> {
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(self.path(key)?)
+            .await?;
+        file.write_all(bytes).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Distinguishes keys generated within the same millisecond, e.g. for the
+/// files of a single upload.
+static KEY_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// A unique, URL and filesystem safe key for an uploaded file, e.g. `1663766400000-7-my-photo.png`.
+pub fn storage_key(filename: &str) -> String {
+    let (stem, extension) = match filename.rsplit_once('.') {
+        Some((stem, extension)) => (stem, Some(extension)),
+        None => (filename, None),
+    };
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let sequence = KEY_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let mut key = format!("{}-{}-{}", millis, sequence, slugify!(stem));
+    if let Some(extension) = extension.map(|it| slugify!(it)).filter(|it| !it.is_empty()) {
+        key.push('.');
+        key.push_str(&extension);
+    }
+
+    key
+}
+
+/// Width and height of raster images, `None` for anything that isn't one.
+pub fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    image::io::Reader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Whether browsers may display the file inline. Everything else, including
+/// SVG and HTML which can run scripts on our origin, is served as a download.
+pub fn is_inline_safe(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp" | "image/avif" | "application/pdf"
+    ) || mime_type.starts_with("video/")
+        || mime_type.starts_with("audio/")
+}
@@ -0,0 +1,136 @@
+use std::io::Cursor;
+
+use anyhow::{bail, Error};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageOutputFormat};
+use serde::Deserialize;
+
+/// Widths and heights derivatives can be requested in, so the cache can't be
+/// filled with every possible size. Mirrored by `IMAGE_WIDTHS` in `js/lib.tsx`.
+pub const ALLOWED_SIZES: &[u32] = &[160, 320, 640, 960, 1280, 1920];
+
+const JPEG_QUALITY: u8 = 80;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Fit {
+    /// scale to fit within the box, keeping the aspect ratio
+    #[default]
+    Contain,
+    /// fill the box, cropping whatever sticks out
+    Cover,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Webp,
+    Png,
+    Jpeg,
+}
+
+impl Format {
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        match ImageFormat::from_mime_type(mime_type)? {
+            ImageFormat::WebP => Some(Format::Webp),
+            ImageFormat::Png => Some(Format::Png),
+            ImageFormat::Jpeg => Some(Format::Jpeg),
+            _ => None,
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Format::Webp => "image/webp",
+            Format::Png => "image/png",
+            Format::Jpeg => "image/jpeg",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Webp => "webp",
+            Format::Png => "png",
+            Format::Jpeg => "jpg",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct Transform {
+    pub w: Option<u32>,
+    pub h: Option<u32>,
+    #[serde(default)]
+    pub fit: Fit,
+    pub fmt: Option<Format>,
+}
+
+impl Transform {
+    pub fn is_identity(&self) -> bool {
+        self.w.is_none() && self.h.is_none() && self.fmt.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        for size in [self.w, self.h].into_iter().flatten() {
+            if !ALLOWED_SIZES.contains(&size) {
+                bail!("size {} is not one of {:?}", size, ALLOWED_SIZES);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// File name of the derivative of the media stored under `key`.
+    pub fn cache_key(&self, key: &str, format: Format) -> String {
+        let size = |it: Option<u32>| it.map(|it| it.to_string()).unwrap_or_else(|| "auto".into());
+        let fit = match self.fit {
+            Fit::Contain => "contain",
+            Fit::Cover => "cover",
+        };
+
+        format!(
+            "{}.{}x{}-{}.{}",
+            key,
+            size(self.w),
+            size(self.h),
+            fit,
+            format.extension()
+        )
+    }
+
+    /// Decode, resize and re-encode an image. Never scales up beyond the original size.
+    pub fn apply(&self, bytes: &[u8], format: Format) -> Result<Vec<u8>, Error> {
+        let image = image::io::Reader::new(Cursor::new(bytes))
+            .with_guessed_format()?
+            .decode()?;
+
+        let width = self.w.map(|it| it.min(image.width()));
+        let height = self.h.map(|it| it.min(image.height()));
+
+        let image = match (width, height) {
+            (Some(width), Some(height)) if self.fit == Fit::Cover => {
+                image.resize_to_fill(width, height, FilterType::Lanczos3)
+            }
+            (None, None) => image,
+            (width, height) => image.resize(
+                width.unwrap_or(u32::MAX),
+                height.unwrap_or(u32::MAX),
+                FilterType::Lanczos3,
+            ),
+        };
+
+        let (image, output_format) = match format {
+            Format::Webp => (image, ImageOutputFormat::WebP),
+            Format::Png => (image, ImageOutputFormat::Png),
+            // JPEG has no alpha channel
+            Format::Jpeg => (
+                DynamicImage::ImageRgb8(image.to_rgb8()),
+                ImageOutputFormat::Jpeg(JPEG_QUALITY),
+            ),
+        };
+
+        let mut buf = Cursor::new(Vec::new());
+        image.write_to(&mut buf, output_format)?;
+        Ok(buf.into_inner())
+    }
+}
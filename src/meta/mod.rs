@@ -0,0 +1,157 @@
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::database::models::{Post, PostType};
+
+/// Longest description derived from a post, in characters.
+const DESCRIPTION_LENGTH: usize = 160;
+
+static SITE: OnceLock<Site> = OnceLock::new();
+
+/// What every page says about the site it's on.
+#[derive(Debug, Clone)]
+struct Site {
+    name: String,
+    /// e.g. `https://example.com`, without a trailing slash
+    url: String,
+    /// e.g. `en` or `de-CH`
+    lang: String,
+}
+
+/// Set the site every page belongs to.
+pub fn init(config: &Config) {
+    SITE.get_or_init(|| Site {
+        name: config.site_name.clone(),
+        url: config.site_url.trim_end_matches('/').to_string(),
+        lang: config.site_lang.clone(),
+    });
+}
+
+fn site() -> &'static Site {
+    SITE.get_or_init(|| Site {
+        name: "Hello World".into(),
+        url: "http://localhost:3000".into(),
+        lang: "en".into(),
+    })
+}
+
+/// What ends up in the `<head>` of a page, passed to templates as `routeMeta`.
+///
+/// These are the defaults the `meta` export of a route starts from, see `Meta` in `js/lib.tsx`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PageMeta {
+    pub site_name: String,
+    /// `lang` of the document and its `Content-Language`
+    pub lang: String,
+    /// without the name of the site, which `Root` adds
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// absolute URL of the page
+    pub canonical: Option<String>,
+    /// absolute URL of an image representing the page
+    pub image: Option<String>,
+    /// Open Graph type
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+impl PageMeta {
+    /// Metadata of a page that's only known by the site it's on, like the admin.
+    pub fn site() -> Self {
+        Self {
+            site_name: site().name.clone(),
+            lang: site().lang.clone(),
+            title: None,
+            description: None,
+            canonical: None,
+            image: None,
+            kind: "website",
+        }
+    }
+
+    /// Metadata of a page served at `path`.
+    pub fn for_path(path: &str) -> Self {
+        Self {
+            canonical: Some(absolute_url(path)),
+            ..Self::site()
+        }
+    }
+
+    /// Metadata of a post, described by the start of its content and shown
+    /// with its first image.
+    pub fn for_post(post: &Post, post_type: Option<&PostType>) -> Self {
+        let (text, image) = post.summary();
+
+        Self {
+            title: Some(post.title.clone()),
+            description: excerpt(&text, DESCRIPTION_LENGTH),
+            canonical: post_type.map(|it| absolute_url(&it.url_for(&post.slug))),
+            image: image.map(|it| absolute_url(&it)),
+            kind: "article",
+            ..Self::site()
+        }
+    }
+}
+
+/// `url` as is when it's absolute, relative to the site otherwise.
+fn absolute_url(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return url.to_string();
+    }
+
+    format!("{}/{}", site().url, url.trim_start_matches('/'))
+}
+
+/// The first `max_chars` of `text` with whitespace collapsed, cut at a word,
+/// or within the first word when that's already too long.
+fn excerpt(text: &str, max_chars: usize) -> Option<String> {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut excerpt = String::new();
+    for word in words {
+        let length = excerpt.chars().count();
+        if length == 0 && word.chars().count() > max_chars - 1 {
+            excerpt.extend(word.chars().take(max_chars - 1));
+            excerpt.push('…');
+            return Some(excerpt);
+        }
+        if length > 0 && length + 1 + word.chars().count() > max_chars - 1 {
+            excerpt.push('…');
+            return Some(excerpt);
+        }
+        if length > 0 {
+            excerpt.push(' ');
+        }
+        excerpt.push_str(word);
+    }
+
+    Some(excerpt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuts_at_a_word() {
+        assert_eq!(excerpt("abc  de\nfgh", 7), Some("abc de…".into()));
+        assert_eq!(excerpt("abc de", 7), Some("abc de".into()));
+    }
+
+    #[test]
+    fn cuts_an_over_long_first_word() {
+        assert_eq!(excerpt("abcdefghij klm", 5), Some("abcd…".into()));
+        assert_eq!(excerpt("ééééééé", 4), Some("ééé…".into()));
+    }
+
+    #[test]
+    fn skips_empty_text() {
+        assert_eq!(excerpt(" \n ", 5), None);
+    }
+}
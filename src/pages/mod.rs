@@ -0,0 +1,168 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+
+use crate::tsx;
+
+static ROUTES: OnceLock<PageRoutes> = OnceLock::new();
+
+/// Keep `routes` for every request, when they can't change while the server runs.
+pub fn init(routes: PageRoutes) {
+    ROUTES.get_or_init(|| routes);
+}
+
+/// The routes passed to [`init`], or those [discovered](PageRoutes::discover)
+/// right now when there are none, so new pages show up without a restart.
+pub fn routes(excluded: &[&str]) -> Cow<'static, PageRoutes> {
+    match ROUTES.get() {
+        Some(routes) => Cow::Borrowed(routes),
+        None => Cow::Owned(PageRoutes::discover(excluded)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Static(String),
+    /// `[name]` in the file name
+    Param(String),
+}
+
+/// A file below `js/routes/` that is served under the URL matching its path,
+/// e.g. `about/contact.tsx` at `/about/contact` and `blog/[slug].tsx` at `/blog/anything`.
+///
+/// Directories in parentheses group routes under a layout without being part
+/// of the URL, e.g. `(site)/about.tsx` is served at `/about`.
+#[derive(Debug, Clone)]
+pub struct PageRoute {
+    pub file: PathBuf,
+    segments: Vec<Segment>,
+}
+
+impl PageRoute {
+    fn new(file: PathBuf) -> Self {
+        let mut segments = file
+            .with_extension("")
+            .iter()
+            .filter(|it| !is_group(&it.to_string_lossy()))
+            .map(|it| {
+                let name = it.to_string_lossy();
+                match name.strip_prefix('[').and_then(|it| it.strip_suffix(']')) {
+                    Some(param) => Segment::Param(param.to_string()),
+                    None => Segment::Static(name.to_string()),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // `blog/index.tsx` is served at `/blog`
+        if segments.last() == Some(&Segment::Static("index".into())) {
+            segments.pop();
+        }
+
+        Self { file, segments }
+    }
+
+    fn matches(&self, path: &[String]) -> Option<BTreeMap<String, String>> {
+        if path.len() != self.segments.len() {
+            return None;
+        }
+
+        let mut params = BTreeMap::new();
+        for (segment, part) in self.segments.iter().zip(path) {
+            match segment {
+                Segment::Static(name) if name == part => {}
+                Segment::Static(_) => return None,
+                Segment::Param(name) => {
+                    params.insert(name.clone(), part.clone());
+                }
+            }
+        }
+
+        Some(params)
+    }
+
+    /// Static segments win over params, from left to right.
+    fn specificity(&self, other: &Self) -> Ordering {
+        let rank = |it: &Segment| match it {
+            Segment::Static(_) => 0,
+            Segment::Param(_) => 1,
+        };
+
+        self.segments
+            .iter()
+            .map(rank)
+            .cmp(other.segments.iter().map(rank))
+    }
+}
+
+/// `(name)`, a directory that only groups routes.
+fn is_group(name: &str) -> bool {
+    name.starts_with('(') && name.ends_with(')')
+}
+
+/// Route data for a page, available through `useRouteData<PageData>()`.
+#[derive(Serialize, Debug)]
+pub struct PageData {
+    pub path: String,
+    pub params: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PageRoutes {
+    routes: Vec<PageRoute>,
+}
+
+impl PageRoutes {
+    /// Every route file except `excluded` ones, which are rendered by hand
+    /// written handlers, and those starting with `_`.
+    pub fn new(files: impl IntoIterator<Item = PathBuf>, excluded: &[&str]) -> Self {
+        let mut routes = files
+            .into_iter()
+            .filter(|file| {
+                let private = file
+                    .file_name()
+                    .is_some_and(|it| it.to_string_lossy().starts_with('_'));
+                !private && !excluded.iter().any(|it| Path::new(it) == file)
+            })
+            .map(PageRoute::new)
+            .collect::<Vec<_>>();
+        routes.sort_by(|a, b| a.specificity(b));
+
+        Self { routes }
+    }
+
+    /// The routes that are currently available: the prebuilt bundles in
+    /// production, the files in `js/routes/` otherwise.
+    pub fn discover(excluded: &[&str]) -> Self {
+        let files = match tsx::bundles::prebuilt() {
+            Some(bundles) => bundles.routes(),
+            None => tsx::bundles::routes().unwrap_or_default(),
+        };
+
+        Self::new(files, excluded)
+    }
+
+    /// Find the route for a URL path, along with its params.
+    pub fn find(&self, path: &str) -> Option<(&PageRoute, PageData)> {
+        let parts = path
+            .split('/')
+            .filter(|it| !it.is_empty())
+            .map(|it| percent_decode_str(it).decode_utf8_lossy().to_string())
+            .collect::<Vec<_>>();
+
+        self.routes.iter().find_map(|route| {
+            let params = route.matches(&parts)?;
+            Some((
+                route,
+                PageData {
+                    path: path.to_string(),
+                    params,
+                },
+            ))
+        })
+    }
+}
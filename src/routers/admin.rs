@@ -2,18 +2,25 @@ use std::path::Path;
 
 use axum::extract::Query;
 use axum::http::StatusCode;
-use axum::response::{IntoResponse, Redirect};
-use axum::{response::Html, routing::get};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::get;
 use axum::{Extension, Form, Router};
 use axum_flash::{Flash, IncomingFlashes};
 use axum_macros::debug_handler;
 use serde::{Deserialize, Serialize};
 use slugify::slugify;
+use surrealdb::sql::{self, Value};
 
-use crate::database::models::{Post, PostType};
-use crate::database::Db;
+use crate::blocks::BlockRegistry;
+use crate::database::models::{Post, PostSort, PostType, SortOrder};
+use crate::database::{post_type_key, Db, Vars};
+use crate::markdown;
+use crate::search::SearchIndex;
 use crate::template;
+use crate::tsx::page::HtmlPage;
+use crate::tsx::RenderError;
 
+use super::search::{run_search, SearchQueryParams};
 use super::util::{TemplateError, TemplateErrors};
 
 pub fn router() -> Router {
@@ -21,70 +28,171 @@ pub fn router() -> Router {
         .route("/", get(admin))
         .route("/posts", get(posts).post(create_post))
         .route("/posts/create", get(create))
+        .route("/search", get(search))
+        .merge(super::media::admin_router())
+        .merge(super::debug::admin_router())
 }
 
 async fn admin(
     Extension(db): Extension<Db>,
     // Extension(runtime): Extension<Runtime>,
-) -> Result<Html<String>, StatusCode> {
+) -> Result<HtmlPage, RenderError> {
     let post_types = db
         .query_first::<Vec<PostType>>("SELECT * FROM postType")
         .await
         .unwrap();
 
     let result = template(
-        Path::new("admin.tsx"),
-        serde_json::to_string(&post_types).unwrap(),
-    );
+        &db,
+        Path::new("admin/index.tsx"),
+        serde_json::to_value(&post_types).unwrap(),
+    )
+    .await?;
 
     Ok(result)
 }
 
+/// The post type with `id` (with or without the `postType:` prefix), bound
+/// rather than formatted into the query since it comes from the request.
+async fn find_post_type(db: &Db, id: &str) -> Option<PostType> {
+    let mut vars = Vars::new();
+    vars.insert("type".into(), post_type_key(id));
+
+    db.query_first_with_vars::<Vec<PostType>>("SELECT * FROM type::thing('postType', $type)", vars)
+        .await
+        .unwrap()
+        .into_iter()
+        .next()
+}
+
 #[derive(Deserialize)]
 struct PostsQueryParams {
     #[serde(rename = "type")]
     post_type: Option<String>,
 }
 
+const DEFAULT_PER_PAGE: usize = 20;
+const MAX_PER_PAGE: usize = 100;
+
+#[derive(Deserialize)]
+struct PostListQueryParams {
+    #[serde(rename = "type")]
+    post_type: Option<String>,
+    page: Option<usize>,
+    per_page: Option<usize>,
+    #[serde(default)]
+    sort: PostSort,
+    #[serde(default)]
+    order: SortOrder,
+    status: Option<String>,
+    search: Option<String>,
+}
+
 #[derive(Serialize)]
 struct Posts {
     post_type: PostType,
     posts: Vec<Post>,
+    total: usize,
+    page: usize,
+    per_page: usize,
+    total_pages: usize,
+    sort: PostSort,
+    order: SortOrder,
+    status: Option<String>,
+    search: Option<String>,
+    errors: Vec<TemplateError>,
+}
+
+#[derive(Deserialize)]
+struct PostCount {
+    total: usize,
 }
 
 async fn posts(
+    inc_flash: IncomingFlashes,
     Extension(db): Extension<Db>,
-    Query(query): Query<PostsQueryParams>,
-) -> impl IntoResponse {
-    let post_type = match query.post_type {
+    Query(query): Query<PostListQueryParams>,
+) -> Result<Response, RenderError> {
+    let post_type = query.post_type.as_deref().unwrap_or("postType:post");
+    let post_type = match find_post_type(&db, post_type).await {
         Some(post_type) => post_type,
-        None => "postType:post".into(),
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
     };
 
-    let maybe_post_type = db
-        .query_first::<Vec<PostType>>(&format!("SELECT * FROM {}", post_type))
+    // empty form fields come through as `Some("")`, treat them as unset
+    let status = query.status.filter(|it| !it.is_empty());
+    let search = query.search.filter(|it| !it.trim().is_empty());
+
+    let mut conditions = vec!["type = type::thing('postType', $type)".to_string()];
+    let mut vars = Vars::new();
+    vars.insert("type".into(), post_type_key(&post_type.id));
+    if let Some(status) = &status {
+        conditions.push("status = $status".into());
+        vars.insert("status".into(), status.clone().into());
+    }
+    if let Some(search) = &search {
+        conditions.push("string::lowercase(title) CONTAINS $search".into());
+        vars.insert("search".into(), search.trim().to_lowercase().into());
+    }
+    let conditions = conditions.join(" AND ");
+
+    // every matching post shares the same type, so grouping by it yields a single count row
+    let counts = db
+        .query_first_with_vars::<Vec<PostCount>>(
+            &format!(
+                "SELECT count() AS total FROM post WHERE {} GROUP BY type",
+                conditions
+            ),
+            vars.clone(),
+        )
         .await
         .unwrap();
+    let total = counts.first().map(|it| it.total).unwrap_or(0);
 
-    let post_type = match maybe_post_type.first() {
-        Some(post_type) => post_type,
-        None => return Err(StatusCode::NOT_FOUND),
-    };
+    let per_page = query
+        .per_page
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+    let total_pages = total.div_ceil(per_page).max(1);
+    let page = query.page.unwrap_or(1).clamp(1, total_pages);
 
     let posts = db
-        .query_first::<Vec<Post>>(&format!("SELECT * FROM post WHERE type = {}", post_type.id))
+        .query_first_with_vars::<Vec<Post>>(
+            &format!(
+                "SELECT * FROM post WHERE {} ORDER BY {} {} LIMIT {} START {}",
+                conditions,
+                query.sort.field(),
+                query.order.keyword(),
+                per_page,
+                (page - 1) * per_page
+            ),
+            vars,
+        )
         .await
         .unwrap();
 
     let result = Posts {
-        post_type: post_type.clone(),
+        post_type,
         posts,
+        total,
+        page,
+        per_page,
+        total_pages,
+        sort: query.sort,
+        order: query.order,
+        status,
+        search,
+        errors: TemplateErrors::from(inc_flash).errors,
     };
 
-    Ok(template(
+    let page = template(
+        &db,
         Path::new("admin/posts.tsx"),
-        serde_json::to_string(&result).unwrap(),
-    ))
+        serde_json::to_value(&result).unwrap(),
+    )
+    .await?;
+
+    Ok(page.into_response())
 }
 
 #[derive(Debug, Deserialize)]
@@ -92,37 +200,103 @@ struct CreatePost {
     title: String,
     content: String,
     post_type: String,
+    /// JSON array of blocks, see `BlockRegistry`
+    blocks: Option<String>,
 }
 
 #[debug_handler]
 async fn create_post(
     Extension(db): Extension<Db>,
+    Extension(search): Extension<SearchIndex>,
+    Extension(registry): Extension<BlockRegistry>,
     Form(input): Form<CreatePost>,
     mut flash: Flash,
 ) -> impl IntoResponse {
+    let post_type = match find_post_type(&db, &input.post_type).await {
+        Some(post_type) => post_type,
+        None => {
+            flash.error("Unknown post type");
+            return Redirect::to("/admin");
+        }
+    };
+    // only the id of a post type that exists ends up in a URL
+    let create_url = format!("/admin/posts/create?type={}", post_type.id);
+
+    let blocks = match input.blocks.as_deref().map(str::trim) {
+        Some(json) if !json.is_empty() => match registry.parse(json) {
+            Ok(blocks) => Some(blocks),
+            Err(errors) => {
+                for error in errors {
+                    flash.error(error);
+                }
+                return Redirect::to(&create_url);
+            }
+        },
+        _ => None,
+    };
+
+    let policy = post_type.sanitize.clone().unwrap_or_default();
+    let (rendered, report) = markdown::render(&input.content, &policy);
+    if !report.is_empty() {
+        flash.warning(report.message());
+    }
+
+    let mut vars = Vars::new();
+    vars.insert("title".into(), input.title.clone().into());
+    vars.insert("content".into(), input.content.clone().into());
+    vars.insert("slug".into(), slugify!(&input.title).into());
+    vars.insert("type".into(), post_type_key(&post_type.id));
+    vars.insert(
+        "blocks".into(),
+        match &blocks {
+            Some(blocks) => sql::json(&serde_json::to_string(blocks).unwrap()).unwrap(),
+            None => Value::None,
+        },
+    );
+    vars.insert(
+        "rendered".into(),
+        sql::json(&serde_json::to_string(&rendered).unwrap()).unwrap(),
+    );
+
     let res = db
-        .query(&format!(
+        .query_with_vars(
             r#"
         CREATE post SET
-        title = '{}', 
-        content = '{}', 
-        slug = '{}',
+        title = $title,
+        content = $content,
+        slug = $slug,
+        blocks = $blocks,
+        rendered = $rendered,
         created_at = time::now(),
         status = 'published',
-        type = {}
+        type = type::thing('postType', $type)
         "#,
-            input.title,
-            input.content,
-            slugify!(&input.title),
-            input.post_type
-        ))
+            vars,
+        )
         .await;
 
-    if res.is_err() {
-        flash.error("Error creating post");
-        Redirect::to("/admin/posts/create")
-    } else {
-        Redirect::to("/admin/posts")
+    match res {
+        Ok(results) => {
+            let created = results
+                .first()
+                .and_then(|it| {
+                    serde_json::from_value::<Vec<Post>>(serde_json::to_value(it).ok()?).ok()
+                })
+                .unwrap_or_default();
+            // the post is saved either way, a stale index shouldn't fail the request
+            for post in &created {
+                if let Err(err) = search.index_post(post) {
+                    tracing::error!("Failed to index {}: {}", post.id, err);
+                    flash.warning("The post was saved but couldn't be added to the search index");
+                }
+            }
+
+            Redirect::to(&format!("/admin/posts?type={}", post_type.id))
+        }
+        Err(_) => {
+            flash.error("Error creating post");
+            Redirect::to(&create_url)
+        }
     }
 }
 
@@ -136,28 +310,39 @@ async fn create(
     inc_flash: IncomingFlashes,
     Query(query): Query<PostsQueryParams>,
     Extension(db): Extension<Db>,
-) -> impl IntoResponse {
-    let post_type = match query.post_type {
-        Some(post_type) => post_type,
-        None => "postType:post".into(),
-    };
-
-    let maybe_post_type = db
-        .query_first::<Vec<PostType>>(&format!("SELECT * FROM {}", post_type))
-        .await
-        .unwrap();
-
-    let post_type = match maybe_post_type.first() {
+) -> Result<Response, RenderError> {
+    let post_type = query.post_type.as_deref().unwrap_or("postType:post");
+    let post_type = match find_post_type(&db, post_type).await {
         Some(post_type) => post_type,
-        None => return Err(StatusCode::NOT_FOUND),
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
     };
 
-    Ok(template(
+    let page = template(
+        &db,
         Path::new("admin/posts/create.tsx"),
-        serde_json::to_string(&CreateResponse {
-            post_type: post_type.clone(),
+        serde_json::to_value(&CreateResponse {
+            post_type,
             errors: TemplateErrors::from(inc_flash).errors,
         })
         .unwrap(),
-    ))
+    )
+    .await?;
+
+    Ok(page.into_response())
+}
+
+async fn search(
+    Extension(db): Extension<Db>,
+    Extension(index): Extension<SearchIndex>,
+    Query(query): Query<SearchQueryParams>,
+) -> Result<HtmlPage, RenderError> {
+    // unlike the public search, drafts show up here as well
+    let results = run_search(&db, &index, query, false).await;
+
+    template(
+        &db,
+        Path::new("admin/search.tsx"),
+        serde_json::to_value(&results).unwrap(),
+    )
+    .await
 }
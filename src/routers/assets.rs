@@ -0,0 +1,83 @@
+use axum::extract::Path;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+
+use crate::assets;
+
+pub fn router() -> Router {
+    Router::new().route("/assets/*name", get(serve))
+}
+
+async fn serve(
+    Path(name): Path<String>,
+    request_headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let name = name.trim_start_matches('/');
+
+    let (etag, fingerprinted, mime_type, bytes) = match assets::manifest().resolve(name) {
+        Some((asset, fingerprinted)) => {
+            if is_not_modified(&request_headers, &asset.etag) {
+                return Ok((
+                    StatusCode::NOT_MODIFIED,
+                    cache_headers(&asset.etag, fingerprinted),
+                )
+                    .into_response());
+            }
+
+            let bytes = tokio::fs::read(&asset.path)
+                .await
+                .map_err(|_| StatusCode::NOT_FOUND)?;
+            (
+                asset.etag.clone(),
+                fingerprinted,
+                asset.mime_type.clone(),
+                bytes,
+            )
+        }
+        // stylesheets generated from the CSS imports of routes only have a fingerprinted name
+        None => {
+            let (etag, css) = assets::stylesheet(name).ok_or(StatusCode::NOT_FOUND)?;
+            if is_not_modified(&request_headers, &etag) {
+                return Ok((StatusCode::NOT_MODIFIED, cache_headers(&etag, true)).into_response());
+            }
+
+            (etag, true, "text/css".to_string(), css.into_bytes())
+        }
+    };
+
+    let mut headers = cache_headers(&etag, fingerprinted);
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(&mime_type).unwrap(),
+    );
+
+    Ok((StatusCode::OK, headers, bytes).into_response())
+}
+
+fn cache_headers(etag: &str, fingerprinted: bool) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(if fingerprinted {
+            "public, max-age=31536000, immutable"
+        } else {
+            // the plain name keeps working but has to be revalidated
+            "public, no-cache"
+        }),
+    );
+    headers.insert(header::ETAG, HeaderValue::from_str(etag).unwrap());
+
+    headers
+}
+
+fn is_not_modified(request_headers: &HeaderMap, etag: &str) -> bool {
+    request_headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|it| it.to_str().ok())
+        .is_some_and(|it| {
+            it.split(',')
+                .any(|tag| tag.trim().trim_start_matches("W/") == etag)
+        })
+}
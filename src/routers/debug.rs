@@ -0,0 +1,56 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::Query;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+
+use crate::tsx::{self, compile_app};
+
+/// Tools for working on templates, nested under `/admin` by the admin router.
+pub fn admin_router() -> Router {
+    Router::new().route("/debug/bundle", get(bundle))
+}
+
+#[derive(Deserialize)]
+struct BundleQueryParams {
+    /// path below `js/routes/`, e.g. `admin/posts.tsx`
+    route: String,
+    /// return the source map instead of the bundle
+    #[serde(default)]
+    map: bool,
+}
+
+/// The bundle a route compiles to, or was compiled to by `experimental-cms build`.
+async fn bundle(Query(query): Query<BundleQueryParams>) -> Result<impl IntoResponse, StatusCode> {
+    let route = PathBuf::from(&query.route);
+
+    let app = match tsx::bundles::prebuilt() {
+        Some(bundles) => bundles.get(&route).ok_or(StatusCode::NOT_FOUND)?,
+        None => {
+            let inside_routes = route
+                .components()
+                .all(|it| matches!(it, Component::Normal(_)));
+            if !inside_routes || !Path::new("js/routes").join(&route).is_file() {
+                return Err(StatusCode::NOT_FOUND);
+            }
+
+            Arc::new(compile_app(&route, tsx::options()))
+        }
+    };
+
+    Ok(if query.map {
+        (
+            [(header::CONTENT_TYPE, "application/json")],
+            app.source_map.clone(),
+        )
+    } else {
+        (
+            [(header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+            app.js.clone(),
+        )
+    })
+}
@@ -0,0 +1,117 @@
+use std::any::Any;
+use std::path::Path;
+
+use axum::body::{boxed, Empty};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+use crate::config::Environment;
+use crate::database::Db;
+use crate::template;
+use crate::tsx::RenderError;
+
+/// Rendered for responses without a page of their own, see [`render_error_pages`].
+const NOT_FOUND_ROUTE: &str = "(site)/404.tsx";
+const SERVER_ERROR_ROUTE: &str = "(site)/500.tsx";
+
+/// Route data of `404.tsx` and `500.tsx`, available through `useRouteData<ErrorPageData>()`.
+#[derive(Serialize, Debug)]
+pub struct ErrorPageData {
+    pub path: String,
+    pub status: u16,
+    /// what went wrong, only while developing since it leaks source paths
+    pub error: Option<ErrorDetails>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorDetails {
+    pub message: String,
+    /// `file:line:column` in the TSX sources, if known
+    pub location: Option<String>,
+    pub stack: String,
+}
+
+/// What a handler panicked with, left on the response of [`panic_response`].
+#[derive(Debug, Clone)]
+struct Panic(String);
+
+/// The response to a handler that panicked, for `CatchPanicLayer`. It's bare
+/// like a `StatusCode`, so [`render_error_pages`] turns it into a page.
+pub fn panic_response(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => "the handler panicked".into(),
+        },
+    };
+
+    let mut response = Response::new(boxed(Empty::new()));
+    *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+    response.extensions_mut().insert(Panic(message));
+    response
+}
+
+/// Render `js/routes/(site)/404.tsx` for a not found and `js/routes/(site)/500.tsx` for a
+/// server error, unless the handler already answered with a page of its own.
+///
+/// Has to run inside the `Extension` layer of the [`Db`].
+pub async fn render_error_pages<B>(request: Request<B>, next: Next<B>) -> Response {
+    let db = request.extensions().get::<Db>().cloned();
+    let path = request.uri().path().to_string();
+    let response = next.run(request).await;
+
+    let status = response.status();
+    let route = match status {
+        StatusCode::NOT_FOUND => NOT_FOUND_ROUTE,
+        status if status.is_server_error() => SERVER_ERROR_ROUTE,
+        _ => return response,
+    };
+
+    let render_error = response.extensions().get::<RenderError>();
+    let panic = response.extensions().get::<Panic>();
+    // only bare statuses, failed renders and panics, not e.g. a JSON error of an API
+    let bare = !response.headers().contains_key(CONTENT_TYPE);
+    if !bare && render_error.is_none() {
+        return response;
+    }
+
+    let error = match (render_error, panic) {
+        (Some(err), _) => Some(ErrorDetails {
+            message: format!("failed to render {}: {}", err.route.display(), err.message),
+            location: err.location.clone(),
+            stack: err.stack.clone(),
+        }),
+        (None, Some(Panic(message))) => Some(ErrorDetails {
+            message: message.clone(),
+            location: None,
+            stack: String::new(),
+        }),
+        (None, None) => None,
+    };
+    let data = ErrorPageData {
+        path,
+        status: status.as_u16(),
+        error: error.filter(|_| Environment::current() == Environment::Development),
+    };
+
+    let db = match db {
+        Some(db) => db,
+        None => return response,
+    };
+    // a failing error page must not hide the error it was rendered for
+    match template(&db, Path::new(route), serde_json::to_value(&data).unwrap()).await {
+        Ok(page) => {
+            let mut page = page.into_response();
+            *page.status_mut() = status;
+            page
+        }
+        Err(err) => {
+            tracing::error!("{}", err);
+            response
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use axum::extract::{ContentLengthLimit, Multipart, Path as UrlPath, Query};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Redirect};
+use axum::routing::get;
+use axum::{Extension, Router};
+use axum_flash::{Flash, IncomingFlashes};
+use serde::Serialize;
+use surrealdb::sql::Value;
+
+use crate::config::Config;
+use crate::database::models::Media;
+use crate::database::{Db, Vars};
+use crate::media::resize::{Format, Transform};
+use crate::media::{self, SharedStorage};
+use crate::template;
+use crate::tsx::page::HtmlPage;
+use crate::tsx::RenderError;
+
+use super::util::{TemplateError, TemplateErrors};
+
+const MAX_UPLOAD_SIZE: u64 = 25 * 1024 * 1024;
+
+/// keeps the temporary files of concurrent writes of the same cached image apart
+static CACHE_WRITE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Public serving of uploaded files and resized images.
+pub fn router() -> Router {
+    Router::new()
+        .route("/uploads/:key", get(serve))
+        .route("/media/:id", get(image))
+}
+
+/// The media library, nested under `/admin` by the admin router.
+pub fn admin_router() -> Router {
+    Router::new().route("/media", get(library).post(upload))
+}
+
+#[derive(Serialize)]
+struct MediaLibrary {
+    media: Vec<Media>,
+    errors: Vec<TemplateError>,
+}
+
+async fn library(
+    inc_flash: IncomingFlashes,
+    Extension(db): Extension<Db>,
+) -> Result<HtmlPage, RenderError> {
+    let media = db
+        .query_first::<Vec<Media>>("SELECT * FROM media ORDER BY created_at DESC")
+        .await
+        .unwrap();
+
+    template(
+        &db,
+        Path::new("admin/media.tsx"),
+        serde_json::to_value(&MediaLibrary {
+            media,
+            errors: TemplateErrors::from(inc_flash).errors,
+        })
+        .unwrap(),
+    )
+    .await
+}
+
+async fn upload(
+    Extension(db): Extension<Db>,
+    Extension(storage): Extension<SharedStorage>,
+    mut flash: Flash,
+    ContentLengthLimit(mut multipart): ContentLengthLimit<Multipart, MAX_UPLOAD_SIZE>,
+) -> impl IntoResponse {
+    let mut uploaded = 0;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => {
+                // the rest of the body can't be read either
+                flash.error(format!("Error reading the upload: {}", err));
+                break;
+            }
+        };
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let filename = match field.file_name() {
+            Some(filename) if !filename.is_empty() => filename.to_string(),
+            _ => continue,
+        };
+        let mime_type = match field.content_type() {
+            Some(mime_type) if mime_type != "application/octet-stream" => mime_type.to_string(),
+            _ => mime_guess::from_path(&filename)
+                .first_or_octet_stream()
+                .to_string(),
+        };
+        let bytes = match field.bytes().await {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                flash.error(format!("Error reading {}", filename));
+                continue;
+            }
+        };
+
+        let key = media::storage_key(&filename);
+        if storage.put(&key, &bytes).await.is_err() {
+            flash.error(format!("Error storing {}", filename));
+            continue;
+        }
+
+        let (width, height) = match media::dimensions(&bytes) {
+            Some((width, height)) => (Value::from(width), Value::from(height)),
+            None => (Value::None, Value::None),
+        };
+
+        let mut vars = Vars::new();
+        vars.insert("filename".into(), filename.clone().into());
+        vars.insert("key".into(), key.clone().into());
+        vars.insert("mime_type".into(), mime_type.into());
+        vars.insert("size".into(), bytes.len().into());
+        vars.insert("width".into(), width);
+        vars.insert("height".into(), height);
+
+        let res = db
+            .query_with_vars(
+                r#"
+        CREATE media SET
+        filename = $filename,
+        key = $key,
+        mime_type = $mime_type,
+        size = $size,
+        width = $width,
+        height = $height,
+        created_at = time::now()
+        "#,
+                vars,
+            )
+            .await;
+
+        if res.is_err() {
+            // don't leave files behind that nothing refers to
+            let _ = storage.delete(&key).await;
+            flash.error(format!("Error saving {}", filename));
+            continue;
+        }
+
+        uploaded += 1;
+    }
+
+    if uploaded > 0 {
+        flash.success(format!("Uploaded {} file(s)", uploaded));
+    }
+
+    Redirect::to("/admin/media")
+}
+
+async fn serve(
+    UrlPath(key): UrlPath<String>,
+    Extension(db): Extension<Db>,
+    Extension(storage): Extension<SharedStorage>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let mut vars = Vars::new();
+    vars.insert("key".into(), key.clone().into());
+
+    let media = db
+        .query_first_with_vars::<Vec<Media>>("SELECT * FROM media WHERE key = $key", vars)
+        .await
+        .unwrap();
+    let media = media.first().ok_or(StatusCode::NOT_FOUND)?;
+
+    let bytes = storage
+        .get(&media.key)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut headers = immutable_headers(&media.mime_type);
+    if !media::is_inline_safe(&media.mime_type) {
+        headers.insert(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_static("attachment"),
+        );
+    }
+
+    Ok((headers, bytes))
+}
+
+/// Serve a resized and/or converted version of an image, e.g. `/media/abc?w=640&fmt=webp`.
+async fn image(
+    UrlPath(id): UrlPath<String>,
+    Query(transform): Query<Transform>,
+    Extension(db): Extension<Db>,
+    Extension(storage): Extension<SharedStorage>,
+    Extension(config): Extension<Config>,
+) -> Result<impl IntoResponse, StatusCode> {
+    transform.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut vars = Vars::new();
+    vars.insert("id".into(), id.into());
+
+    let media = db
+        .query_first_with_vars::<Vec<Media>>("SELECT * FROM type::thing('media', $id)", vars)
+        .await
+        .unwrap();
+    let media = media.first().ok_or(StatusCode::NOT_FOUND)?;
+
+    if transform.is_identity() {
+        return Ok(Redirect::temporary(&format!("/uploads/{}", media.key)).into_response());
+    }
+
+    let format = match transform
+        .fmt
+        .or_else(|| Format::from_mime_type(&media.mime_type))
+    {
+        Some(format) if media.width.is_some() => format,
+        // not an image we can decode or encode, only the original is available
+        _ => return Err(StatusCode::NOT_FOUND),
+    };
+
+    let cache_path = config
+        .image_cache_dir
+        .join(transform.cache_key(&media.key, format));
+
+    let bytes = match tokio::fs::read(&cache_path).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            let original = storage
+                .get(&media.key)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .ok_or(StatusCode::NOT_FOUND)?;
+
+            let bytes = tokio::task::spawn_blocking(move || transform.apply(&original, format))
+                .await
+                .unwrap()
+                .map_err(|err| {
+                    tracing::error!("Failed to resize {}: {}", media.key, err);
+                    StatusCode::UNPROCESSABLE_ENTITY
+                })?;
+
+            // a failed write only means the next request has to resize again
+            let _ = tokio::fs::create_dir_all(&config.image_cache_dir).await;
+            if let Err(err) = write_cache(&cache_path, &bytes).await {
+                tracing::warn!("Failed to cache {}: {}", cache_path.display(), err);
+            }
+
+            bytes
+        }
+    };
+
+    Ok((immutable_headers(format.mime_type()), bytes).into_response())
+}
+
+/// Write a resized image to the cache next to its path and rename it into
+/// place, so a concurrent request never serves half an image as immutable.
+async fn write_cache(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension(format!(
+        "{}.tmp",
+        CACHE_WRITE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    tokio::fs::write(&tmp, bytes).await?;
+    if let Err(err) = tokio::fs::rename(&tmp, path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Headers for content that never changes under its URL.
+fn immutable_headers(mime_type: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(mime_type)
+            .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
+    );
+    // keys are never reused, so the response can be cached forever
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=31536000, immutable"),
+    );
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+
+    headers
+}
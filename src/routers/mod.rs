@@ -1,2 +1,7 @@
 pub mod admin;
+pub mod assets;
+pub mod debug;
+pub mod error_pages;
+pub mod media;
+pub mod search;
 pub mod util;
@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use axum::extract::Query;
+use axum::routing::get;
+use axum::{Extension, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::PostType;
+use crate::database::Db;
+use crate::search::{SearchHit, SearchIndex};
+use crate::template;
+use crate::tsx::page::HtmlPage;
+use crate::tsx::RenderError;
+
+const RESULT_LIMIT: usize = 20;
+
+pub fn router() -> Router {
+    Router::new().route("/search", get(search))
+}
+
+#[derive(Deserialize)]
+pub struct SearchQueryParams {
+    q: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    hit: SearchHit,
+    url: String,
+}
+
+#[derive(Serialize)]
+pub struct SearchResults {
+    query: String,
+    results: Vec<SearchResult>,
+}
+
+/// Run a search and resolve the public URL of every hit.
+pub async fn run_search(
+    db: &Db,
+    index: &SearchIndex,
+    query: SearchQueryParams,
+    published_only: bool,
+) -> SearchResults {
+    let query = query.q.unwrap_or_default().trim().to_string();
+    if query.is_empty() {
+        return SearchResults {
+            query,
+            results: vec![],
+        };
+    }
+
+    let post_types = db
+        .query_first::<Vec<PostType>>("SELECT * FROM postType")
+        .await
+        .unwrap();
+
+    let results = index
+        .search(&query, published_only, RESULT_LIMIT)
+        .unwrap()
+        .into_iter()
+        .map(|hit| {
+            let url = match post_types.iter().find(|it| it.id == hit.post_type) {
+                Some(post_type) => post_type.url_for(&hit.slug),
+                None => format!("/{}", hit.slug),
+            };
+            SearchResult { hit, url }
+        })
+        .collect();
+
+    SearchResults { query, results }
+}
+
+async fn search(
+    Extension(db): Extension<Db>,
+    Extension(index): Extension<SearchIndex>,
+    Query(query): Query<SearchQueryParams>,
+) -> Result<HtmlPage, RenderError> {
+    let results = run_search(&db, &index, query, true).await;
+
+    template(
+        &db,
+        Path::new("(site)/search.tsx"),
+        serde_json::to_value(&results).unwrap(),
+    )
+    .await
+}
@@ -0,0 +1,360 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use serde::{Deserialize, Serialize};
+
+/// Tags whose content is dropped along with the tag, unless a policy allows them.
+const CLEAN_CONTENT_TAGS: &[&str] = &["script", "style"];
+
+/// Which HTML may end up in rendered content, configured per post type through
+/// the `sanitize` field of a `postType` record.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SanitizePolicy {
+    /// tags that are kept, any other tag is removed but its text is kept
+    pub tags: BTreeSet<String>,
+    /// attributes allowed on every tag
+    #[serde(default)]
+    pub generic_attributes: BTreeSet<String>,
+    /// attributes allowed only on a specific tag
+    #[serde(default)]
+    pub tag_attributes: BTreeMap<String, BTreeSet<String>>,
+    /// classes allowed on a specific tag, any other class is removed; a
+    /// trailing `*` allows every class starting with what's before it
+    #[serde(default)]
+    pub tag_classes: BTreeMap<String, BTreeSet<String>>,
+    /// `style` declarations allowed on a specific tag, e.g. `text-align: left`,
+    /// any other declaration is removed
+    #[serde(default)]
+    pub tag_styles: BTreeMap<String, BTreeSet<String>>,
+    /// schemes allowed in `href` and `src`, relative URLs are always allowed
+    #[serde(default = "default_url_schemes")]
+    pub url_schemes: BTreeSet<String>,
+}
+
+fn default_url_schemes() -> BTreeSet<String> {
+    set(&["http", "https", "mailto"])
+}
+
+fn set(items: &[&str]) -> BTreeSet<String> {
+    items.iter().map(|it| it.to_string()).collect()
+}
+
+impl Default for SanitizePolicy {
+    /// Everything the Markdown renderer produces, without scripting and with
+    /// no styling beyond the alignment of table columns.
+    fn default() -> Self {
+        let mut tag_attributes = BTreeMap::new();
+        tag_attributes.insert("a".into(), set(&["href", "title"]));
+        tag_attributes.insert(
+            "img".into(),
+            set(&["src", "alt", "title", "width", "height"]),
+        );
+        tag_attributes.insert("div".into(), set(&["id", "class"]));
+        tag_attributes.insert("sup".into(), set(&["class"]));
+        tag_attributes.insert("th".into(), set(&["colspan", "rowspan"]));
+        tag_attributes.insert("td".into(), set(&["colspan", "rowspan"]));
+        for heading in ["h1", "h2", "h3", "h4", "h5", "h6"] {
+            tag_attributes.insert(heading.into(), set(&["id"]));
+        }
+
+        // the language of a fenced code block
+        let mut tag_classes = BTreeMap::new();
+        tag_classes.insert("code".into(), set(&["language-*"]));
+
+        let mut tag_styles = BTreeMap::new();
+        for cell in ["th", "td"] {
+            tag_styles.insert(
+                cell.into(),
+                set(&[
+                    "text-align: left",
+                    "text-align: center",
+                    "text-align: right",
+                ]),
+            );
+        }
+
+        Self {
+            tags: set(&[
+                "a",
+                "abbr",
+                "b",
+                "blockquote",
+                "br",
+                "code",
+                "del",
+                "div",
+                "em",
+                "figcaption",
+                "figure",
+                "h1",
+                "h2",
+                "h3",
+                "h4",
+                "h5",
+                "h6",
+                "hr",
+                "i",
+                "img",
+                "li",
+                "ol",
+                "p",
+                "pre",
+                "s",
+                "span",
+                "strong",
+                "sub",
+                "sup",
+                "table",
+                "tbody",
+                "td",
+                "th",
+                "thead",
+                "tr",
+                "ul",
+            ]),
+            generic_attributes: set(&["lang", "title"]),
+            tag_attributes,
+            tag_classes,
+            tag_styles,
+            url_schemes: default_url_schemes(),
+        }
+    }
+}
+
+/// What a [`SanitizePolicy`] removed from a piece of HTML.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SanitizeReport {
+    pub removed_tags: BTreeSet<String>,
+    /// formatted as `tag[attribute]`
+    pub removed_attributes: BTreeSet<String>,
+}
+
+impl SanitizeReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed_tags.is_empty() && self.removed_attributes.is_empty()
+    }
+
+    /// Human readable summary, suitable for a flash message.
+    pub fn message(&self) -> String {
+        let items = self
+            .removed_tags
+            .iter()
+            .map(|it| format!("<{}>", it))
+            .chain(self.removed_attributes.iter().cloned())
+            .collect::<Vec<_>>();
+
+        format!("Removed disallowed HTML: {}", items.join(", "))
+    }
+}
+
+pub struct Sanitized {
+    pub html: String,
+    pub report: SanitizeReport,
+}
+
+impl SanitizePolicy {
+    pub fn clean(&self, html: &str) -> Sanitized {
+        Sanitized {
+            html: self.builder().clean(html).to_string(),
+            report: self.report(html),
+        }
+    }
+
+    fn builder(&self) -> ammonia::Builder<'_> {
+        let mut tag_attributes = self
+            .tag_attributes
+            .iter()
+            .map(|(tag, attributes)| {
+                (
+                    tag.as_str(),
+                    attributes
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<HashSet<_>>(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        for tag in self.tag_classes.keys() {
+            tag_attributes
+                .entry(tag.as_str())
+                .or_default()
+                .insert("class");
+        }
+        for tag in self.tag_styles.keys() {
+            tag_attributes
+                .entry(tag.as_str())
+                .or_default()
+                .insert("style");
+        }
+
+        let tag_classes = self.tag_classes.clone();
+        let tag_styles = self.tag_styles.clone();
+
+        let mut builder = ammonia::Builder::default();
+        builder
+            .tags(self.tags.iter().map(String::as_str).collect())
+            .clean_content_tags(
+                CLEAN_CONTENT_TAGS
+                    .iter()
+                    .copied()
+                    .filter(|it| !self.tags.contains(*it))
+                    .collect(),
+            )
+            .generic_attributes(self.generic_attributes.iter().map(String::as_str).collect())
+            .tag_attributes(tag_attributes)
+            .attribute_filter(move |tag, attribute, value| {
+                let kept = match attribute {
+                    "class" => match tag_classes.get(tag) {
+                        Some(classes) => kept_classes(classes, value).join(" "),
+                        None => return Some(value.into()),
+                    },
+                    "style" => match tag_styles.get(tag) {
+                        Some(styles) => kept_declarations(styles, value).join("; "),
+                        None => return Some(value.into()),
+                    },
+                    _ => return Some(value.into()),
+                };
+
+                (!kept.is_empty()).then(|| kept.into())
+            })
+            .url_schemes(self.url_schemes.iter().map(String::as_str).collect());
+
+        // ammonia refuses to run when `rel` is both allowed and enforced
+        if self.allows_attribute("a", "rel") {
+            builder.link_rel(None);
+        }
+
+        builder
+    }
+
+    fn allows_attribute(&self, tag: &str, attribute: &str) -> bool {
+        self.generic_attributes.contains(attribute)
+            || self
+                .tag_attributes
+                .get(tag)
+                .is_some_and(|it| it.contains(attribute))
+            || (attribute == "class" && self.tag_classes.contains_key(tag))
+            || (attribute == "style" && self.tag_styles.contains_key(tag))
+    }
+
+    /// Whether all of `value` is kept, for an attribute that's allowed.
+    fn allows_value(&self, tag: &str, attribute: &str, value: &str) -> bool {
+        match (
+            attribute,
+            self.tag_classes.get(tag),
+            self.tag_styles.get(tag),
+        ) {
+            ("href" | "src", _, _) => self.allows_url(value),
+            ("class", Some(classes), _) => {
+                kept_classes(classes, value).len() == value.split_whitespace().count()
+            }
+            ("style", _, Some(styles)) => {
+                kept_declarations(styles, value).len() == declarations(value).count()
+            }
+            _ => true,
+        }
+    }
+
+    fn allows_url(&self, url: &str) -> bool {
+        let scheme = match url.split_once(':') {
+            Some((scheme, _)) => scheme,
+            None => return true,
+        };
+
+        // a colon after the first slash, `?` or `#` belongs to a relative URL
+        if scheme.contains(['/', '?', '#']) {
+            return true;
+        }
+
+        self.url_schemes.contains(&scheme.trim().to_lowercase())
+    }
+
+    fn report(&self, html: &str) -> SanitizeReport {
+        let mut queue = BufferQueue::new();
+        queue.push_back(StrTendril::from(html));
+
+        let mut tokenizer = Tokenizer::new(TagCollector::default(), TokenizerOpts::default());
+        let _ = tokenizer.feed(&mut queue);
+        tokenizer.end();
+
+        let mut report = SanitizeReport::default();
+        for tag in tokenizer.sink.tags {
+            let name = tag.name.to_string();
+            if !self.tags.contains(&name) {
+                report.removed_tags.insert(name);
+                continue;
+            }
+
+            for attribute in &tag.attrs {
+                let attribute_name = attribute.name.local.to_string();
+                let allowed = self.allows_attribute(&name, &attribute_name)
+                    && self.allows_value(&name, &attribute_name, &attribute.value);
+
+                if !allowed {
+                    report
+                        .removed_attributes
+                        .insert(format!("{}[{}]", name, attribute_name));
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// The classes in `value` matching one of `patterns`, see [`SanitizePolicy::tag_classes`].
+fn kept_classes<'a>(patterns: &BTreeSet<String>, value: &'a str) -> Vec<&'a str> {
+    value
+        .split_whitespace()
+        .filter(|class| {
+            patterns
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => class.starts_with(prefix),
+                    None => pattern == class,
+                })
+        })
+        .collect()
+}
+
+/// The declarations in `value` among `allowed`, see [`SanitizePolicy::tag_styles`].
+fn kept_declarations(allowed: &BTreeSet<String>, value: &str) -> Vec<String> {
+    declarations(value)
+        .filter(|it| allowed.contains(it))
+        .collect()
+}
+
+/// The declarations of a `style` attribute, formatted as `name: value`.
+fn declarations(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(';')
+        .filter(|it| !it.trim().is_empty())
+        .map(|it| match it.split_once(':') {
+            Some((name, value)) => {
+                format!("{}: {}", name.trim().to_ascii_lowercase(), value.trim())
+            }
+            None => it.trim().to_string(),
+        })
+}
+
+#[derive(Default)]
+struct TagCollector {
+    tags: Vec<Tag>,
+}
+
+impl TokenSink for TagCollector {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        if let Token::TagToken(tag) = token {
+            if tag.kind == TagKind::StartTag {
+                self.tags.push(tag);
+            }
+        }
+
+        TokenSinkResult::Continue
+    }
+}
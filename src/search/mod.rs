@@ -0,0 +1,172 @@
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value, STORED, STRING, TEXT};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+use crate::database::models::Post;
+use crate::database::Db;
+
+const WRITER_MEMORY: usize = 15_000_000;
+const SNIPPET_LENGTH: usize = 200;
+
+#[derive(Clone, Copy)]
+struct Fields {
+    id: Field,
+    title: Field,
+    content: Field,
+    slug: Field,
+    post_type: Field,
+    status: Field,
+}
+
+/// In-memory full-text index over post titles and content.
+///
+/// The database stays the source of truth: the index is rebuilt from it on
+/// startup and every write to `post` has to be mirrored here.
+#[derive(Clone)]
+pub struct SearchIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Arc<Mutex<IndexWriter>>,
+    fields: Fields,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchHit {
+    pub id: String,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+    #[serde(rename = "type")]
+    pub post_type: String,
+    pub score: f32,
+    /// HTML excerpt of the content with the matched terms wrapped in `<b>`
+    pub snippet: String,
+}
+
+impl SearchIndex {
+    pub fn new() -> tantivy::Result<Self> {
+        let mut schema = Schema::builder();
+        let fields = Fields {
+            id: schema.add_text_field("id", STRING | STORED),
+            title: schema.add_text_field("title", TEXT | STORED),
+            content: schema.add_text_field("content", TEXT | STORED),
+            slug: schema.add_text_field("slug", STORED),
+            post_type: schema.add_text_field("type", STRING | STORED),
+            status: schema.add_text_field("status", STRING | STORED),
+        };
+
+        let index = Index::create_in_ram(schema.build());
+        let writer = index.writer_with_num_threads(1, WRITER_MEMORY)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+
+        Ok(Self {
+            index,
+            reader,
+            writer: Arc::new(Mutex::new(writer)),
+            fields,
+        })
+    }
+
+    /// Drop everything in the index and load all posts from the database.
+    pub async fn rebuild(&self, db: &Db) -> tantivy::Result<()> {
+        let posts = db
+            .query_first::<Vec<Post>>("SELECT * FROM post")
+            .await
+            .unwrap();
+
+        let mut writer = self.writer.lock().unwrap();
+        writer.delete_all_documents()?;
+        for post in &posts {
+            writer.add_document(self.document(post))?;
+        }
+        writer.commit()?;
+        self.reader.reload()?;
+
+        tracing::debug!("Indexed {} posts for search", posts.len());
+        Ok(())
+    }
+
+    /// Add a post to the index, replacing a previously indexed version of it.
+    pub fn index_post(&self, post: &Post) -> tantivy::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.delete_term(Term::from_field_text(self.fields.id, &post.id));
+        writer.add_document(self.document(post))?;
+        writer.commit()?;
+        self.reader.reload()
+    }
+
+    pub fn search(
+        &self,
+        query: &str,
+        published_only: bool,
+        limit: usize,
+    ) -> tantivy::Result<Vec<SearchHit>> {
+        let fields = self.fields;
+
+        let mut parser = QueryParser::for_index(&self.index, vec![fields.title, fields.content]);
+        parser.set_field_boost(fields.title, 2.0);
+        // visitor input shouldn't be able to produce a parse error, unparseable parts are ignored
+        let (text_query, _) = parser.parse_query_lenient(query);
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, text_query)];
+        if published_only {
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(fields.status, "published"),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+        let query = BooleanQuery::new(clauses);
+
+        let searcher = self.reader.searcher();
+        let mut snippets = SnippetGenerator::create(&searcher, &query, fields.content)?;
+        snippets.set_max_num_chars(SNIPPET_LENGTH);
+
+        let mut hits = Vec::new();
+        for (score, address) in searcher.search(&query, &TopDocs::with_limit(limit))? {
+            let doc = searcher.doc::<TantivyDocument>(address)?;
+            let text = |field: Field| {
+                doc.get_first(field)
+                    .and_then(|it| it.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+
+            hits.push(SearchHit {
+                id: text(fields.id),
+                title: text(fields.title),
+                slug: text(fields.slug),
+                status: text(fields.status),
+                post_type: text(fields.post_type),
+                score,
+                snippet: snippets.snippet_from_doc(&doc).to_html(),
+            });
+        }
+
+        Ok(hits)
+    }
+
+    /// The post as it's searched, by its text rather than its markup, so
+    /// snippets don't show Markdown syntax.
+    fn document(&self, post: &Post) -> TantivyDocument {
+        let fields = self.fields;
+        doc!(
+            fields.id => post.id.as_str(),
+            fields.title => post.title.as_str(),
+            fields.content => post.summary().0,
+            fields.slug => post.slug.as_str(),
+            fields.post_type => post.post_type.as_str(),
+            fields.status => post.status.as_str(),
+        )
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{bail, Context, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::loader::Loader;
+
+use super::{compile_app, CompileOptions, CompiledApp};
+
+const ROUTES_DIR: &str = "js/routes";
+const MANIFEST_FILE: &str = "manifest.json";
+const ROUTE_EXTENSIONS: &[&str] = &["tsx", "ts", "jsx", "js"];
+/// File name (without extension) of the layout wrapping the routes of a directory.
+const LAYOUT_NAME: &str = "_layout";
+
+static PREBUILT: OnceLock<PrebuiltBundles> = OnceLock::new();
+
+/// Written by `experimental-cms build` next to the bundles it lists.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BundleManifest {
+    /// route path below `js/routes/`, e.g. `admin/posts.tsx`
+    pub routes: BTreeMap<String, BundleFiles>,
+}
+
+/// Files of a single route, relative to the manifest.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleFiles {
+    pub js: String,
+    pub source_map: String,
+    pub css: Option<String>,
+    /// in bytes
+    pub js_size: usize,
+    /// in bytes, 0 without `css`
+    pub css_size: usize,
+    /// the `loader` exported by the route, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader: Option<Loader>,
+}
+
+impl BundleManifest {
+    /// A table of the size of every route, for the output of `experimental-cms build`.
+    pub fn size_report(&self) -> String {
+        let width = self
+            .routes
+            .keys()
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+            .max("Total".len());
+
+        let mut report = format!("{:width$}  {:>10}  {:>10}\n", "Route", "JS", "CSS");
+        for (route, files) in &self.routes {
+            report.push_str(&format!(
+                "{:width$}  {:>10}  {:>10}\n",
+                route,
+                format_size(files.js_size),
+                format_size(files.css_size)
+            ));
+        }
+        report.push_str(&format!(
+            "{:width$}  {:>10}  {:>10}\n",
+            "Total",
+            format_size(self.routes.values().map(|it| it.js_size).sum()),
+            format_size(self.routes.values().map(|it| it.css_size).sum())
+        ));
+
+        report
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    }
+}
+
+/// Every route below `js/routes/`, as paths relative to it. Files starting
+/// with `_`, like layouts, aren't routes.
+pub fn routes() -> Result<Vec<PathBuf>, Error> {
+    let mut routes = vec![];
+    collect_routes(Path::new(ROUTES_DIR), Path::new(""), &mut routes)?;
+    routes.sort();
+
+    Ok(routes)
+}
+
+fn collect_routes(root: &Path, dir: &Path, routes: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in std::fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let path = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_routes(root, &path, routes)?;
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        let is_route = !name.ends_with(".d.ts")
+            && !name.starts_with('_')
+            && path
+                .extension()
+                .is_some_and(|it| ROUTE_EXTENSIONS.iter().any(|ext| it == *ext));
+        if is_route {
+            routes.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// The layouts wrapping `route` (below `js/routes/`), outermost first: the
+/// `_layout` file of `js/routes/` and of every directory down to the route's.
+///
+/// A layout for only some of the routes of a directory goes in a `(group)`
+/// directory, see [`crate::pages::PageRoute`].
+pub fn layouts(route: &Path) -> Vec<PathBuf> {
+    let mut dirs = route.ancestors().skip(1).collect::<Vec<_>>();
+    dirs.reverse();
+
+    dirs.into_iter()
+        .filter_map(|dir| {
+            ROUTE_EXTENSIONS
+                .iter()
+                .map(|ext| {
+                    Path::new(ROUTES_DIR)
+                        .join(dir)
+                        .join(format!("{}.{}", LAYOUT_NAME, ext))
+                })
+                .find(|it| it.is_file())
+        })
+        .collect()
+}
+
+/// Bundle every route into `out_dir` and write a manifest listing them.
+pub fn build(out_dir: &Path, options: CompileOptions) -> Result<BundleManifest, Error> {
+    let mut manifest = BundleManifest::default();
+
+    for route in routes()? {
+        let app = compile_app(&route, options);
+        let name = route.to_string_lossy().replace('\\', "/");
+        let stem = Path::new("routes").join(&route).with_extension("");
+
+        let files = BundleFiles {
+            js: format!("{}.js", stem.display()),
+            source_map: format!("{}.js.map", stem.display()),
+            css: (!app.css.is_empty()).then(|| format!("{}.css", stem.display())),
+            js_size: app.js.len(),
+            css_size: app.css.len(),
+            loader: app
+                .loader
+                .clone()
+                .transpose()
+                .map_err(|err| Error::msg(format!("route {}: {}", name, err)))?,
+        };
+
+        std::fs::create_dir_all(out_dir.join(&stem).parent().unwrap())?;
+        std::fs::write(out_dir.join(&files.js), &app.js)?;
+        std::fs::write(out_dir.join(&files.source_map), &app.source_map)?;
+        if let Some(css) = &files.css {
+            std::fs::write(out_dir.join(css), &app.css)?;
+        }
+
+        manifest.routes.insert(name, files);
+    }
+
+    std::fs::write(
+        out_dir.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    Ok(manifest)
+}
+
+/// Bundles read from the output of [`build`], used instead of compiling routes on request.
+#[derive(Default)]
+pub struct PrebuiltBundles {
+    apps: HashMap<PathBuf, Arc<CompiledApp>>,
+}
+
+impl PrebuiltBundles {
+    /// Load every bundle listed in the manifest in `dir`, failing if one of
+    /// `required` isn't among them.
+    pub fn load(dir: &Path, required: &[&str]) -> Result<Self, Error> {
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest: BundleManifest = serde_json::from_str(
+            &std::fs::read_to_string(&manifest_path)
+                .with_context(|| format!("failed to read {}", manifest_path.display()))?,
+        )?;
+
+        for route in required {
+            if !manifest.routes.contains_key(*route) {
+                bail!(
+                    "route {} is missing from {}",
+                    route,
+                    manifest_path.display()
+                );
+            }
+        }
+
+        let mut apps = HashMap::new();
+        for (route, files) in manifest.routes {
+            let read = |file: &str| {
+                std::fs::read_to_string(dir.join(file))
+                    .with_context(|| format!("failed to read bundle of {}", route))
+            };
+
+            let app = CompiledApp {
+                js: read(&files.js)?,
+                css: match &files.css {
+                    Some(css) => read(css)?,
+                    None => String::new(),
+                },
+                source_map: read(&files.source_map)?,
+                loader: files.loader.map(Ok),
+            };
+            apps.insert(PathBuf::from(route), Arc::new(app));
+        }
+
+        Ok(Self { apps })
+    }
+
+    pub fn get(&self, route: &Path) -> Option<Arc<CompiledApp>> {
+        self.apps.get(route).cloned()
+    }
+
+    pub fn routes(&self) -> Vec<PathBuf> {
+        self.apps.keys().cloned().collect()
+    }
+}
+
+/// Serve routes from `bundles` from now on, see [`prebuilt`].
+pub fn init(bundles: PrebuiltBundles) -> &'static PrebuiltBundles {
+    tracing::info!("Loaded {} prebuilt bundles", bundles.apps.len());
+    PREBUILT.get_or_init(|| bundles)
+}
+
+/// The bundles passed to [`init`], `None` when routes are compiled on request.
+pub fn prebuilt() -> Option<&'static PrebuiltBundles> {
+    PREBUILT.get()
+}
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+
+use sha2::{Digest, Sha256};
+
+const SUFFIX_LENGTH: usize = 6;
+
+/// Suffix appended to the class names of a CSS module, derived from its path
+/// so the names stay the same from one render to the next.
+pub fn scope_suffix(path: &Path) -> String {
+    let cwd = std::env::current_dir().unwrap();
+    let path = path.strip_prefix(&cwd).unwrap_or(path);
+
+    let hash = format!("{:x}", Sha256::digest(path.to_string_lossy().as_bytes()));
+    hash[..SUFFIX_LENGTH].to_string()
+}
+
+/// Rename every class selector in `source` to `<name>_<suffix>`, returning the
+/// rewritten stylesheet and the original to scoped name of each class.
+pub fn scope_classes(source: &str, suffix: &str) -> (String, BTreeMap<String, String>) {
+    let mut css = String::with_capacity(source.len());
+    let mut classes = BTreeMap::new();
+    // everything since the last rule or declaration ended, a selector when a block follows
+    let mut prelude = String::new();
+
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                prelude.push(c);
+                take_comment(&mut chars, &mut prelude);
+            }
+            '"' | '\'' => {
+                prelude.push(c);
+                take_string(c, &mut chars, &mut prelude);
+            }
+            // with CSS nesting, the block of a rule may hold rules as well as declarations
+            '{' => {
+                css.push_str(&scope_prelude(&prelude, suffix, &mut classes));
+                prelude.clear();
+                css.push(c);
+            }
+            ';' | '}' => {
+                css.push_str(&prelude);
+                prelude.clear();
+                css.push(c);
+            }
+            _ => prelude.push(c),
+        }
+    }
+    css.push_str(&prelude);
+
+    (css, classes)
+}
+
+/// `prelude` of a block with its class selectors scoped, as is for an at-rule.
+fn scope_prelude(prelude: &str, suffix: &str, classes: &mut BTreeMap<String, String>) -> String {
+    let mut scoped = String::with_capacity(prelude.len());
+    let mut at_start = true;
+
+    let mut chars = prelude.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                scoped.push(c);
+                take_comment(&mut chars, &mut scoped);
+            }
+            '@' if at_start => return prelude.to_string(),
+            '"' | '\'' => {
+                at_start = false;
+                scoped.push(c);
+                take_string(c, &mut chars, &mut scoped);
+            }
+            '.' if chars.peek().is_some_and(|it| is_name_start(*it)) => {
+                at_start = false;
+                let mut name = String::new();
+                while let Some(next) = chars.next_if(|it| is_name_char(*it)) {
+                    name.push(next);
+                }
+
+                let class = format!("{}_{}", name, suffix);
+                scoped.push('.');
+                scoped.push_str(&class);
+                classes.insert(name, class);
+            }
+            c => {
+                at_start &= c.is_whitespace();
+                scoped.push(c);
+            }
+        }
+    }
+
+    scoped
+}
+
+/// Copy the rest of a comment whose `/` has been read to `out`.
+fn take_comment(chars: &mut Peekable<Chars>, out: &mut String) {
+    // the `*` opening the comment can't also close it
+    out.extend(chars.next());
+    let mut previous = ' ';
+    for c in chars.by_ref() {
+        out.push(c);
+        if previous == '*' && c == '/' {
+            break;
+        }
+        previous = c;
+    }
+}
+
+/// Copy the rest of a string opened by `quote` to `out`.
+fn take_string(quote: char, chars: &mut Peekable<Chars>, out: &mut String) {
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c == '\\' {
+            out.extend(chars.next());
+        } else if c == quote {
+            break;
+        }
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '-' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scoped(source: &str) -> String {
+        scope_classes(source, "x").0
+    }
+
+    #[test]
+    fn scopes_class_selectors() {
+        let (css, classes) = scope_classes(".a .b:hover, div.c > .a {}", "x");
+
+        assert_eq!(css, ".a_x .b_x:hover, div.c_x > .a_x {}");
+        assert_eq!(
+            classes.into_iter().collect::<Vec<_>>(),
+            [("a", "a_x"), ("b", "b_x"), ("c", "c_x")].map(|(a, b)| (a.to_string(), b.to_string()))
+        );
+    }
+
+    #[test]
+    fn scopes_inside_nested_at_rules() {
+        assert_eq!(
+            scoped(
+                "@media (min-width: 40.5em) { @supports (display: grid) { .a { color: red } } }"
+            ),
+            "@media (min-width: 40.5em) { @supports (display: grid) { .a_x { color: red } } }"
+        );
+    }
+
+    #[test]
+    fn keeps_declarations() {
+        assert_eq!(
+            scoped(".a { padding: 0.5rem; background: url(img.png) }"),
+            ".a_x { padding: 0.5rem; background: url(img.png) }"
+        );
+    }
+
+    #[test]
+    fn keeps_strings_and_comments() {
+        assert_eq!(
+            scoped("/* .b { */ .a[title=\".c {\"] { content: '.d;}' } /*/ .e */"),
+            "/* .b { */ .a_x[title=\".c {\"] { content: '.d;}' } /*/ .e */"
+        );
+    }
+
+    #[test]
+    fn scopes_nested_rules() {
+        assert_eq!(
+            scoped(".a { color: red; .b { color: blue } &.c:hover { color: green } }"),
+            ".a_x { color: red; .b_x { color: blue } &.c_x:hover { color: green } }"
+        );
+    }
+
+    #[test]
+    fn keeps_keyframe_selectors() {
+        assert_eq!(
+            scoped("@keyframes fade { 0% { opacity: 0 } 12.5% { opacity: 1 } }"),
+            "@keyframes fade { 0% { opacity: 0 } 12.5% { opacity: 1 } }"
+        );
+    }
+}
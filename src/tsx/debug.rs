@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+use anyhow::Error;
+
+use super::CompiledApp;
+
+static DEBUG_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+/// keeps the temporary files of concurrent dumps of the same route apart
+static DUMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Dump the bundle of every rendered route to `dir`, or nothing when `None`.
+pub fn init(dir: Option<PathBuf>) {
+    if let Some(dir) = &dir {
+        tracing::info!("Writing route bundles to {}", dir.display());
+    }
+
+    DEBUG_DIR.get_or_init(|| dir);
+}
+
+/// Write the bundle of `route` (and its source map) to the debug directory, if enabled.
+///
+/// A failing dump is logged rather than failing the render.
+pub fn dump(route: &Path, app: &CompiledApp) {
+    let dir = match DEBUG_DIR.get() {
+        Some(Some(dir)) => dir,
+        _ => return,
+    };
+
+    if let Err(err) = write(&dir.join(route).with_extension("js"), app) {
+        tracing::warn!("Failed to dump bundle of {}: {}", route.display(), err);
+    }
+}
+
+fn write(path: &Path, app: &CompiledApp) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let map_path = path.with_extension("js.map");
+    let map_name = map_path.file_name().unwrap().to_string_lossy();
+    let js = format!("{}\n//# sourceMappingURL={}\n", app.js, map_name);
+
+    // write next to the target and rename, so a reader never sees half a file
+    for (path, contents) in [(path, js.as_str()), (&map_path, app.source_map.as_str())] {
+        let tmp = path.with_extension(format!(
+            "{}.tmp",
+            DUMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&tmp, contents)?;
+        std::fs::rename(&tmp, path)?;
+    }
+
+    Ok(())
+}
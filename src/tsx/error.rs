@@ -0,0 +1,154 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+
+use super::limits::LimitExceeded;
+use super::CompiledApp;
+use crate::config::Environment;
+
+/// Name QuickJS gives to code run through `Ctx::eval`, as it shows up in stack traces.
+const EVAL_SCRIPT: &str = "eval_script:";
+
+/// An exception thrown while rendering a route, with every position in it
+/// pointing at the original TSX sources instead of the bundle.
+///
+/// Its response carries a copy of it, so layers can tell what went wrong.
+#[derive(Debug, Clone)]
+pub struct RenderError {
+    pub route: PathBuf,
+    pub message: String,
+    /// `file:line:column` the exception was thrown at, if known
+    pub location: Option<String>,
+    pub stack: String,
+    /// set when the render was stopped by one of its limits
+    pub limit: Option<LimitExceeded>,
+}
+
+impl RenderError {
+    pub fn new(route: &Path, app: &CompiledApp, error: rquickjs::Error) -> Self {
+        match error {
+            rquickjs::Error::Exception {
+                message,
+                line,
+                stack,
+                ..
+            } => Self {
+                route: route.to_path_buf(),
+                message,
+                location: u32::try_from(line)
+                    .ok()
+                    .and_then(|line| app.original_position(line, None)),
+                stack: stack
+                    .lines()
+                    .map(|frame| remap_frame(app, frame))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                limit: None,
+            },
+            error => Self {
+                route: route.to_path_buf(),
+                message: error.to_string(),
+                location: None,
+                stack: String::new(),
+                limit: None,
+            },
+        }
+    }
+
+    /// A failure before the route ran, e.g. an invalid `loader`.
+    pub fn from_message(route: &Path, message: impl Into<String>) -> Self {
+        Self {
+            route: route.to_path_buf(),
+            message: message.into(),
+            location: None,
+            stack: String::new(),
+            limit: None,
+        }
+    }
+
+    /// Mark the error as caused by `limit`, see [`LimitedRuntime::exceeded`](super::limits::LimitedRuntime::exceeded).
+    pub fn with_limit(mut self, limit: Option<LimitExceeded>) -> Self {
+        if let Some(limit) = limit {
+            self.message = format!("{} ({})", limit, self.message);
+        }
+        self.limit = limit;
+        self
+    }
+}
+
+/// Rewrite `at Root (eval_script:1339)` to `at Root (js/root.tsx:12:7)`.
+fn remap_frame(app: &CompiledApp, frame: &str) -> String {
+    let start = match frame.find(EVAL_SCRIPT) {
+        Some(start) => start,
+        None => return frame.to_string(),
+    };
+    let position_start = start + EVAL_SCRIPT.len();
+    let end = frame[position_start..]
+        .find(')')
+        .map(|it| position_start + it)
+        .unwrap_or(frame.len());
+
+    let mut numbers = frame[position_start..end]
+        .split(':')
+        .map(|it| it.trim().parse::<u32>().ok());
+    let line = numbers.next().flatten();
+    let column = numbers.next().flatten();
+
+    match line.and_then(|line| app.original_position(line, column)) {
+        Some(position) => format!("{}{}{}", &frame[..start], position, &frame[end..]),
+        None => frame.to_string(),
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to render {}: {}",
+            self.route.display(),
+            self.message
+        )?;
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+        if !self.stack.is_empty() {
+            write!(f, "\n{}", self.stack)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl IntoResponse for RenderError {
+    fn into_response(self) -> Response {
+        tracing::error!("{}", self);
+
+        // a timeout may just be the server being busy, other failures are the template's
+        let (status, title) = match self.limit {
+            Some(LimitExceeded::Timeout) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable")
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error"),
+        };
+
+        // the stack trace is only shown while developing, it leaks source paths
+        let body = if Environment::current() == Environment::Development {
+            format!(
+                "<!DOCTYPE html><html><head><title>Render error</title></head><body><h1>Failed to render {}</h1><p>{}</p><pre>{}</pre></body></html>",
+                ammonia::clean_text(&self.route.to_string_lossy()),
+                ammonia::clean_text(&self.message),
+                ammonia::clean_text(&self.stack),
+            )
+        } else {
+            format!("<h1>{}</h1>", title)
+        };
+
+        let mut response = (status, Html(body)).into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
+}
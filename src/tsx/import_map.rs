@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::Error;
+use serde::Deserialize;
+
+static IMPORT_MAP: OnceLock<ImportMap> = OnceLock::new();
+
+/// Load the import map of the project in `root`, used by every bundle.
+pub fn init(root: &Path) -> Result<(), Error> {
+    let import_map = ImportMap::load(root)?;
+    IMPORT_MAP.get_or_init(|| import_map);
+    Ok(())
+}
+
+/// The import map loaded by [`init`], an empty one before that.
+pub fn import_map() -> &'static ImportMap {
+    IMPORT_MAP.get_or_init(ImportMap::default)
+}
+
+/// Where bare imports like `react` or `$components/Button` point to.
+///
+/// Read from `compilerOptions.paths` in `tsconfig.json`, so the editor and the
+/// bundler agree, and from a Deno style `import_map.json`, which wins when
+/// both map the same specifier.
+#[derive(Debug, Default, Clone)]
+pub struct ImportMap {
+    /// specifier pattern (with at most one `*`) to target (with a `*` if the pattern has one)
+    entries: BTreeMap<String, PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct TsConfig {
+    #[serde(rename = "compilerOptions", default)]
+    compiler_options: CompilerOptions,
+}
+
+#[derive(Deserialize, Default)]
+struct CompilerOptions {
+    #[serde(rename = "baseUrl")]
+    base_url: Option<PathBuf>,
+    #[serde(default)]
+    paths: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct ImportMapFile {
+    #[serde(default)]
+    imports: BTreeMap<String, String>,
+}
+
+impl ImportMap {
+    /// Load `tsconfig.json` and `import_map.json` from `root`, either may be missing.
+    pub fn load(root: &Path) -> Result<Self, Error> {
+        let mut import_map = Self::default();
+
+        let tsconfig = root.join("tsconfig.json");
+        if tsconfig.is_file() {
+            import_map.add_tsconfig(root, &std::fs::read_to_string(tsconfig)?)?;
+        }
+
+        let file = root.join("import_map.json");
+        if file.is_file() {
+            import_map.add_import_map(root, &std::fs::read_to_string(file)?)?;
+        }
+
+        Ok(import_map)
+    }
+
+    fn add_tsconfig(&mut self, root: &Path, json: &str) -> Result<(), Error> {
+        let options = serde_json::from_str::<TsConfig>(&strip_jsonc(json))?.compiler_options;
+        let base_dir = root.join(options.base_url.unwrap_or_default());
+
+        for (pattern, targets) in options.paths {
+            // TypeScript falls back to later targets, a bundle only needs the first
+            if let Some(target) = targets.first() {
+                self.entries.insert(pattern, base_dir.join(target));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_import_map(&mut self, root: &Path, json: &str) -> Result<(), Error> {
+        for (specifier, target) in serde_json::from_str::<ImportMapFile>(json)?.imports {
+            // a trailing slash maps every specifier starting with it
+            if specifier.ends_with('/') && target.ends_with('/') {
+                self.entries
+                    .insert(format!("{}*", specifier), root.join(format!("{}*", target)));
+            } else {
+                self.entries.insert(specifier, root.join(target));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn insert(&mut self, specifier: impl Into<String>, target: impl Into<PathBuf>) {
+        self.entries.insert(specifier.into(), target.into());
+    }
+
+    /// The path `specifier` maps to, which may still need an extension.
+    pub fn resolve(&self, specifier: &str) -> Option<PathBuf> {
+        if let Some(target) = self.entries.get(specifier) {
+            return Some(target.clone());
+        }
+
+        // like TypeScript, the pattern with the longest prefix wins
+        self.entries
+            .iter()
+            .filter_map(|(pattern, target)| {
+                let (prefix, suffix) = pattern.split_once('*')?;
+                let matched = specifier.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                Some((prefix.len(), matched, target))
+            })
+            .max_by_key(|(prefix_length, _, _)| *prefix_length)
+            .map(|(_, matched, target)| {
+                PathBuf::from(target.to_string_lossy().replacen('*', matched, 1))
+            })
+    }
+}
+
+/// `source` without the comments and trailing commas `tsconfig.json` may have,
+/// which `serde_json` rejects.
+fn strip_jsonc(source: &str) -> String {
+    let mut json = String::with_capacity(source.len());
+    // position of a comma in `json` that's dropped if a `}` or `]` follows
+    let mut comma = None;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                comma = None;
+                json.push(c);
+                while let Some(c) = chars.next() {
+                    json.push(c);
+                    match c {
+                        '\\' => json.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        json.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = None;
+                for c in chars.by_ref() {
+                    if previous == Some('*') && c == '/' {
+                        break;
+                    }
+                    previous = Some(c);
+                }
+                json.push(' ');
+            }
+            '}' | ']' => {
+                if let Some(position) = comma.take() {
+                    json.remove(position);
+                }
+                json.push(c);
+            }
+            ',' => {
+                comma = Some(json.len());
+                json.push(c);
+            }
+            c if c.is_whitespace() => json.push(c),
+            c => {
+                comma = None;
+                json.push(c);
+            }
+        }
+    }
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn parse(jsonc: &str) -> Value {
+        serde_json::from_str(&strip_jsonc(jsonc)).unwrap()
+    }
+
+    #[test]
+    fn strips_comments() {
+        let jsonc = r#"{
+            // the editor reads this too
+            "a": /* inline */ 1
+        }"#;
+
+        assert_eq!(parse(jsonc), json!({ "a": 1 }));
+    }
+
+    #[test]
+    fn strips_trailing_commas() {
+        let jsonc = r#"{ "a": [1, 2,], "b": { "c": 3, /* last */ }, }"#;
+
+        assert_eq!(parse(jsonc), json!({ "a": [1, 2], "b": { "c": 3 } }));
+    }
+
+    #[test]
+    fn keeps_strings() {
+        let jsonc = r#"{ "a": "http://example.com/*", "b": ",]", "c": "\"//" }"#;
+
+        assert_eq!(
+            parse(jsonc),
+            json!({ "a": "http://example.com/*", "b": ",]", "c": "\"//" })
+        );
+    }
+
+    #[test]
+    fn reads_tsconfig_paths() {
+        let mut import_map = ImportMap::default();
+        import_map
+            .add_tsconfig(
+                Path::new("/app"),
+                r#"{
+                    "compilerOptions": {
+                        "baseUrl": "./js",
+                        // like the editor sees them
+                        "paths": { "$components/*": ["./components/*"], },
+                    },
+                }"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            import_map.resolve("$components/Button"),
+            Some(PathBuf::from("/app/./js/./components/Button"))
+        );
+    }
+}
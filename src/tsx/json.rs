@@ -0,0 +1,30 @@
+use rquickjs::{Array, Ctx, Object};
+use serde_json::Value;
+
+/// Convert JSON into a QuickJS value, so data reaches a template without being
+/// spliced into JavaScript source.
+pub fn to_js<'js>(ctx: Ctx<'js>, value: &Value) -> rquickjs::Result<rquickjs::Value<'js>> {
+    Ok(match value {
+        Value::Null => rquickjs::Value::new_null(ctx),
+        Value::Bool(value) => rquickjs::Value::new_bool(ctx, *value),
+        Value::Number(number) => match number.as_i64().and_then(|it| i32::try_from(it).ok()) {
+            Some(int) => rquickjs::Value::new_int(ctx, int),
+            None => rquickjs::Value::new_float(ctx, number.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(value) => rquickjs::String::from_str(ctx, value)?.into_value(),
+        Value::Array(items) => {
+            let array = Array::new(ctx)?;
+            for (index, item) in items.iter().enumerate() {
+                array.set(index, to_js(ctx, item)?)?;
+            }
+            array.into_value()
+        }
+        Value::Object(map) => {
+            let object = Object::new(ctx)?;
+            for (key, value) in map {
+                object.set(key.as_str(), to_js(ctx, value)?)?;
+            }
+            object.into_value()
+        }
+    })
+}
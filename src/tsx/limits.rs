@@ -0,0 +1,113 @@
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use rquickjs::Runtime;
+
+static LIMITS: OnceLock<RenderLimits> = OnceLock::new();
+
+/// Bounds on a single render, so a template that loops forever or allocates
+/// without end fails its request instead of taking down the worker.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderLimits {
+    /// wall time evaluating the bundle may take, including calls into `cms`
+    pub timeout: Duration,
+    /// in bytes
+    pub memory_limit: usize,
+    /// in bytes, has to stay well below the stack of the threads rendering
+    pub max_stack_size: usize,
+}
+
+impl Default for RenderLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            memory_limit: 64 << 20,
+            max_stack_size: 512 << 10,
+        }
+    }
+}
+
+/// Set the limits of every render from now on.
+pub fn init(limits: RenderLimits) {
+    LIMITS.get_or_init(|| limits);
+}
+
+/// The limits passed to [`init`], the defaults before that.
+pub fn limits() -> RenderLimits {
+    LIMITS.get().copied().unwrap_or_default()
+}
+
+/// The limit a render ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    Timeout,
+    Memory,
+    Stack,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limits = limits();
+        match self {
+            LimitExceeded::Timeout => write!(f, "took longer than {:?}", limits.timeout),
+            LimitExceeded::Memory => write!(
+                f,
+                "used more than {} MiB of memory",
+                limits.memory_limit >> 20
+            ),
+            LimitExceeded::Stack => write!(
+                f,
+                "used more than {} KiB of stack",
+                limits.max_stack_size >> 10
+            ),
+        }
+    }
+}
+
+/// A QuickJS runtime for a single render, interrupting whatever runs in it
+/// once the timeout has passed since it was created.
+pub struct LimitedRuntime {
+    pub runtime: Runtime,
+    timed_out: Rc<Cell<bool>>,
+}
+
+impl LimitedRuntime {
+    pub fn new(limits: RenderLimits) -> Self {
+        let runtime = Runtime::new().unwrap();
+        runtime.set_memory_limit(limits.memory_limit);
+        runtime.set_max_stack_size(limits.max_stack_size);
+
+        let deadline = Instant::now() + limits.timeout;
+        let timed_out = Rc::new(Cell::new(false));
+        let flag = timed_out.clone();
+        // the interruption can't be caught by the template
+        runtime.set_interrupt_handler(Some(Box::new(move || {
+            let exceeded = Instant::now() >= deadline;
+            flag.set(flag.get() || exceeded);
+            exceeded
+        })));
+
+        Self { runtime, timed_out }
+    }
+
+    /// The limit that caused `error`, if any.
+    pub fn exceeded(&self, error: &rquickjs::Error) -> Option<LimitExceeded> {
+        if self.timed_out.get() {
+            return Some(LimitExceeded::Timeout);
+        }
+
+        match error {
+            rquickjs::Error::Allocation => Some(LimitExceeded::Memory),
+            rquickjs::Error::Exception { message, .. } if message == "out of memory" => {
+                Some(LimitExceeded::Memory)
+            }
+            rquickjs::Error::Exception { message, .. } if message == "stack overflow" => {
+                Some(LimitExceeded::Stack)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use serde_json::{Map, Number, Value};
+use swc_ecma_ast::*;
+
+/// The value of `export const <name> = <literal>` in `module` as JSON, if it's exported.
+///
+/// Only literals are understood, as the value is read without running any code.
+pub fn exported_literal(module: &Module, name: &str) -> Option<Result<Value, String>> {
+    module.body.iter().find_map(|item| {
+        let var = match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                decl: Decl::Var(var),
+                ..
+            })) => var,
+            _ => return None,
+        };
+
+        var.decls
+            .iter()
+            .find_map(|declarator| match &declarator.name {
+                Pat::Ident(ident) if &*ident.id.sym == name => Some(match &declarator.init {
+                    Some(init) => to_json(init).map_err(|err| format!("`{}` {}", name, err)),
+                    None => Err(format!("`{}` has no value", name)),
+                }),
+                _ => None,
+            })
+    })
+}
+
+fn to_json(expr: &Expr) -> Result<Value, String> {
+    match expr {
+        Expr::Lit(Lit::Str(it)) => Ok(Value::String(it.value.to_string())),
+        Expr::Lit(Lit::Bool(it)) => Ok(Value::Bool(it.value)),
+        Expr::Lit(Lit::Null(_)) => Ok(Value::Null),
+        // JS only has floats, but `5` should still deserialize into an integer
+        Expr::Lit(Lit::Num(it)) if it.value.fract() == 0.0 && it.value.abs() < 2f64.powi(53) => {
+            Ok(Value::Number((it.value as i64).into()))
+        }
+        Expr::Lit(Lit::Num(it)) => Number::from_f64(it.value)
+            .map(Value::Number)
+            .ok_or_else(|| "contains a number that isn't finite".to_string()),
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => Ok(Value::String(
+            tpl.quasis
+                .iter()
+                .map(|it| it.cooked.as_ref().unwrap_or(&it.raw).to_string())
+                .collect(),
+        )),
+        Expr::Array(array) => array
+            .elems
+            .iter()
+            .map(|it| match it {
+                Some(ExprOrSpread { spread: None, expr }) => to_json(expr),
+                _ => Err("contains a spread or a hole in an array".to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Expr::Object(object) => {
+            let mut map = Map::new();
+            for prop in &object.props {
+                let (key, value) = match prop {
+                    PropOrSpread::Prop(prop) => match &**prop {
+                        Prop::KeyValue(it) => (&it.key, &it.value),
+                        _ => return Err("contains a property that isn't `key: value`".into()),
+                    },
+                    PropOrSpread::Spread(_) => return Err("contains a spread".into()),
+                };
+                let key = match key {
+                    PropName::Ident(it) => it.sym.to_string(),
+                    PropName::Str(it) => it.value.to_string(),
+                    _ => return Err("contains a computed or numeric key".into()),
+                };
+
+                map.insert(key, to_json(value)?);
+            }
+
+            Ok(Value::Object(map))
+        }
+        Expr::Paren(it) => to_json(&it.expr),
+        // `{ ... } as const` and the like only matter to the type checker
+        Expr::TsAs(it) => to_json(&it.expr),
+        Expr::TsConstAssertion(it) => to_json(&it.expr),
+        Expr::TsTypeAssertion(it) => to_json(&it.expr),
+        _ => Err("isn't a literal".into()),
+    }
+}
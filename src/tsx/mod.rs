@@ -1,23 +1,30 @@
 use std::collections::HashMap;
 
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
 
 use anyhow::{bail, Error};
 
 use path_clean::PathClean;
+use sourcemap::SourceMapBuilder;
 use swc::atoms::js_word;
 use swc_bundler::ModuleRecord;
 use swc_bundler::{Bundler, Load, ModuleData};
 use swc_bundler::{Config, Resolve};
 use swc_common::comments::SingleThreadedComments;
-use swc_common::{sync::Lrc, FileName, FilePathMapping, Globals, SourceMap};
+use swc_common::{sync::Lrc, FileName, FilePathMapping, Globals, SourceMap, GLOBALS};
 use swc_common::{Mark, Span};
 use swc_ecma_ast::*;
 use swc_ecma_codegen::text_writer::JsWriter;
 use swc_ecma_codegen::Emitter;
+use swc_ecma_minifier::optimize;
+use swc_ecma_minifier::option::{
+    CompressOptions, ExtraOptions, MangleOptions, MinifyOptions, TopLevelOptions,
+};
 use swc_ecma_parser::Syntax;
 use swc_ecma_parser::{parse_file_as_module, TsConfig};
 use swc_ecma_transforms_base::{
+    fixer::fixer,
     helpers::{inject_helpers, Helpers, HELPERS},
     resolver,
 };
@@ -26,21 +33,109 @@ use swc_ecma_transforms_react::react;
 use swc_ecma_transforms_typescript::strip;
 use swc_ecma_visit::FoldWith;
 
-pub fn compile_app(path: &Path, data: String) -> String {
+use crate::loader::Loader;
+
+pub mod bundles;
+mod css;
+pub mod debug;
+mod error;
+pub mod import_map;
+pub mod json;
+pub mod limits;
+mod literal;
+pub mod page;
+
+pub use error::RenderError;
+pub use import_map::ImportMap;
+
+/// Imported by `app.tsx` for the layouts of the route, outermost first, see
+/// [`bundles::layouts`]. The module is generated rather than read from disk.
+const LAYOUTS_SPECIFIER: &str = "$layouts";
+
+pub struct CompiledApp {
+    pub js: String,
+    /// every stylesheet imported by the route, concatenated
+    pub css: String,
+    /// source map JSON for `js`, with sources relative to the working directory
+    pub source_map: String,
+    /// the `loader` the route exports, or why it couldn't be read
+    pub loader: Option<Result<Loader, String>>,
+}
+
+impl CompiledApp {
+    /// `file:line:column` in the sources for a 1-based line (and column) of `js`.
+    pub fn original_position(&self, line: u32, column: Option<u32>) -> Option<String> {
+        let line = line.checked_sub(1)?;
+        // only needed when something went wrong, so it's parsed on demand
+        let source_map = sourcemap::SourceMap::from_slice(self.source_map.as_bytes()).ok()?;
+        let token = match column {
+            Some(column) => source_map.lookup_token(line, column.saturating_sub(1))?,
+            None => source_map.tokens().find(|it| it.get_dst_line() == line)?,
+        };
+
+        Some(format!(
+            "{}:{}:{}",
+            PathBuf::from(token.get_source()?).clean().display(),
+            token.get_src_line() + 1,
+            token.get_src_col() + 1
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompileOptions {
+    /// compress and mangle the bundle, dropping code nothing refers to
+    pub minify: bool,
+}
+
+static OPTIONS: OnceLock<CompileOptions> = OnceLock::new();
+
+/// Set the options used for routes compiled on request.
+pub fn init(options: CompileOptions) {
+    OPTIONS.get_or_init(|| options);
+}
+
+/// The options passed to [`init`], the defaults before that.
+pub fn options() -> CompileOptions {
+    OPTIONS.get().copied().unwrap_or_default()
+}
+
+/// Bundle `js/app.tsx` with `path` (below `js/routes/`) as its route, wrapped
+/// in the layouts of its directories.
+///
+/// The bundle reads its data from `globalThis.routeData`, which has to be set
+/// before it's evaluated.
+pub fn compile_app(path: &Path, options: CompileOptions) -> CompiledApp {
     let globals = Globals::new();
     let cm = std::sync::Arc::new(SourceMap::new(FilePathMapping::empty()));
     // let external_modules = vec![];
-    let mut import_map: HashMap<String, PathBuf> = HashMap::new();
-    import_map.insert("react".into(), "./react.js".into());
-    import_map.insert("react-dom".into(), "./react-dom.js".into());
-    import_map.insert("$route".into(), Path::new("./routes/").join(path));
-    import_map.insert("$lib".into(), "./lib.tsx".into());
-
+    let cwd = std::env::current_dir().unwrap();
+    let mut import_map = import_map::import_map().clone();
+    let route = cwd.join("js/routes").join(path).clean();
+    import_map.insert("$route", route.clone());
+    let layouts = bundles::layouts(path)
+        .into_iter()
+        .map(|it| cwd.join(it).clean())
+        .collect();
+    let layouts_module = cwd.join("js/routes/$layouts.ts");
+
+    let css = Arc::new(Mutex::new(Vec::new()));
+    let loader = Arc::new(Mutex::new(None));
     let mut bundler = Bundler::new(
         &globals,
         cm.clone(),
-        PathLoader { cm: cm.clone() },
-        Resolver { import_map },
+        PathLoader {
+            cm: cm.clone(),
+            css: css.clone(),
+            route,
+            loader: loader.clone(),
+            layouts,
+            layouts_module: layouts_module.clone(),
+        },
+        Resolver {
+            import_map,
+            layouts_module,
+        },
         Config {
             require: true,
             disable_inliner: false,
@@ -52,28 +147,111 @@ pub fn compile_app(path: &Path, data: String) -> String {
     entries.insert("main".to_string(), FileName::Real("./js/app.tsx".into()));
     let mut bundles = bundler.bundle(entries).expect("failed to bundle");
     let bundle = bundles.pop().unwrap();
+    let module = if options.minify {
+        minify(&globals, cm.clone(), bundle.module)
+    } else {
+        bundle.module
+    };
     let mut buf = vec![];
-    let s = format!("globalThis.routeData = {};\n", data);
-    buf.append(&mut s.into());
+    let mut mappings = vec![];
 
-    let wr = JsWriter::new(cm.clone(), "\n", &mut buf, None);
+    let wr = JsWriter::new(cm.clone(), "\n", &mut buf, Some(&mut mappings));
 
     let mut emitter = Emitter {
         cfg: swc_ecma_codegen::Config {
-            minify: false,
+            minify: options.minify,
             ..Default::default()
         },
-        cm,
+        cm: cm.clone(),
         comments: None,
         wr,
     };
-    emitter.emit_module(&bundle.module).unwrap();
+    emitter.emit_module(&module).unwrap();
+
+    let css = css
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, css)| css.clone())
+        .collect::<Vec<_>>();
+    let loader = loader.lock().unwrap().take();
+
+    CompiledApp {
+        js: String::from_utf8_lossy(&buf).to_string(),
+        css: css.join("\n"),
+        source_map: relative_source_map(&cm.build_source_map(&mut mappings), &cwd),
+        loader,
+    }
+}
+
+fn minify(globals: &Globals, cm: Lrc<SourceMap>, module: Module) -> Module {
+    GLOBALS.set(globals, || {
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        let module = module.fold_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        let program = optimize(
+            module.into(),
+            cm,
+            None,
+            None,
+            &MinifyOptions {
+                // the bundle is a whole program, so unused top level code can go as well
+                compress: Some(CompressOptions {
+                    top_level: Some(TopLevelOptions { functions: true }),
+                    ..Default::default()
+                }),
+                mangle: Some(MangleOptions {
+                    top_level: true,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            &ExtraOptions {
+                unresolved_mark,
+                top_level_mark,
+            },
+        );
 
-    return String::from_utf8_lossy(&buf).to_string();
+        match program {
+            Program::Module(module) => module.fold_with(&mut fixer(None)),
+            Program::Script(_) => unreachable!(),
+        }
+    })
+}
+
+/// Make the sources of `map` relative to `cwd`, so they don't depend on where
+/// the bundle was built, and serialize it.
+fn relative_source_map(map: &sourcemap::SourceMap, cwd: &Path) -> String {
+    let mut builder = SourceMapBuilder::new(None);
+    for token in map.tokens() {
+        builder.add(
+            token.get_dst_line(),
+            token.get_dst_col(),
+            token.get_src_line(),
+            token.get_src_col(),
+            token.get_source(),
+            token.get_name(),
+        );
+    }
+    builder.strip_prefixes(&[cwd.to_string_lossy()]);
+
+    let mut json = vec![];
+    builder.into_sourcemap().to_writer(&mut json).unwrap();
+    String::from_utf8(json).unwrap()
 }
 
 struct PathLoader {
     cm: Lrc<SourceMap>,
+    /// stylesheets imported anywhere in the module graph, in the order they're first imported
+    css: Arc<Mutex<Vec<(PathBuf, String)>>>,
+    /// path of the route module, whose `loader` export is read into `loader`
+    route: PathBuf,
+    loader: Arc<Mutex<Option<Result<Loader, String>>>>,
+    /// layouts of the route, outermost first
+    layouts: Vec<PathBuf>,
+    /// path `$layouts` resolves to
+    layouts_module: PathBuf,
 }
 
 impl Load for PathLoader {
@@ -81,14 +259,38 @@ impl Load for PathLoader {
         let unresolved_mark = Mark::new();
         let top_level_mark = Mark::new();
 
-        let tsx;
-        let fm = match f {
-            FileName::Real(path) => {
-                tsx = path.to_string_lossy().ends_with(".tsx");
-                self.cm.load_file(path)?
-            }
+        let path = match f {
+            FileName::Real(path) => path,
             _ => unreachable!(),
         };
+        let file_name = path.to_string_lossy();
+
+        let tsx = file_name.ends_with(".tsx");
+        let fm = if file_name.ends_with(".css") {
+            // stylesheets are collected on the side, the module graph only sees their class names
+            let source = std::fs::read_to_string(path)?;
+            let (css, exports) = if file_name.ends_with(".module.css") {
+                css::scope_classes(&source, &css::scope_suffix(path))
+            } else {
+                (source, Default::default())
+            };
+            {
+                let mut stylesheets = self.css.lock().unwrap();
+                if !stylesheets.iter().any(|(it, _)| it == path) {
+                    stylesheets.push((path.clone(), css));
+                }
+            }
+
+            self.cm.new_source_file(
+                f.clone(),
+                format!("export default {};", serde_json::to_string(&exports)?),
+            )
+        } else if *path == self.layouts_module {
+            self.cm
+                .new_source_file(f.clone(), layouts_source(&self.layouts))
+        } else {
+            self.cm.load_file(path)?
+        };
 
         let module = parse_file_as_module(
             &fm,
@@ -103,6 +305,12 @@ impl Load for PathLoader {
         )
         .unwrap();
 
+        if *path == self.route {
+            // an unreadable `loader` fails the render rather than the bundle
+            *self.loader.lock().unwrap() = literal::exported_literal(&module, "loader")
+                .map(|it| it.and_then(Loader::from_value));
+        }
+
         let module = HELPERS.set(&Helpers::new(false), || {
             module
                 .fold_with(&mut resolver(unresolved_mark, top_level_mark, false))
@@ -129,6 +337,24 @@ impl Load for PathLoader {
     }
 }
 
+/// A module exporting the default exports of `layouts` as an array.
+fn layouts_source(layouts: &[PathBuf]) -> String {
+    let mut source = String::new();
+    for (index, layout) in layouts.iter().enumerate() {
+        source.push_str(&format!(
+            "import Layout{} from {};\n",
+            index,
+            serde_json::to_string(&layout.to_string_lossy()).unwrap()
+        ));
+    }
+    let names = (0..layouts.len())
+        .map(|index| format!("Layout{}", index))
+        .collect::<Vec<_>>();
+    source.push_str(&format!("export default [{}];\n", names.join(", ")));
+
+    source
+}
+
 struct Hook;
 
 impl swc_bundler::Hook for Hook {
@@ -168,11 +394,73 @@ impl swc_bundler::Hook for Hook {
 }
 
 pub struct Resolver {
-    import_map: HashMap<String, PathBuf>,
+    import_map: ImportMap,
+    /// see [`LAYOUTS_SPECIFIER`]
+    layouts_module: PathBuf,
 }
 
 static EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "json", "node"];
 
+/// Conditions matched in package.json "exports", most preferred first. Bundles
+/// run in QuickJS, which is closer to a browser than to node.
+static CONDITIONS: &[&str] = &["import", "module", "browser", "default"];
+
+fn read_package_json(path: &Path) -> Result<serde_json::Value, Error> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Split `@scope/name/sub/path` into `@scope/name` and `sub/path`.
+fn split_package_specifier(target: &str) -> (&str, Option<&str>) {
+    let name_segments = if target.starts_with('@') { 2 } else { 1 };
+    match target.match_indices('/').nth(name_segments - 1) {
+        Some((index, _)) => (&target[..index], Some(&target[index + 1..])),
+        None => (target, None),
+    }
+}
+
+/// Find the target of `subpath` in an "exports" value, which is either a
+/// target for `.` or a map of subpaths (possibly `*` patterns) to targets.
+fn resolve_exports(exports: &serde_json::Value, subpath: &str) -> Option<String> {
+    let subpaths = match exports.as_object() {
+        Some(map) if map.keys().all(|it| it.starts_with('.')) => map,
+        _ if subpath == "." => return resolve_export_target(exports, None),
+        _ => return None,
+    };
+
+    if let Some(target) = subpaths.get(subpath) {
+        return resolve_export_target(target, None);
+    }
+
+    // the pattern with the longest prefix wins
+    subpaths
+        .iter()
+        .filter_map(|(pattern, target)| {
+            let (prefix, suffix) = pattern.split_once('*')?;
+            let matched = subpath.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            Some((prefix.len(), matched, target))
+        })
+        .max_by_key(|(prefix_length, _, _)| *prefix_length)
+        .and_then(|(_, matched, target)| resolve_export_target(target, Some(matched)))
+}
+
+fn resolve_export_target(target: &serde_json::Value, matched: Option<&str>) -> Option<String> {
+    match target {
+        serde_json::Value::String(target) => Some(match matched {
+            Some(matched) => target.replace('*', matched),
+            None => target.clone(),
+        }),
+        serde_json::Value::Array(fallbacks) => fallbacks
+            .iter()
+            .find_map(|it| resolve_export_target(it, matched)),
+        // serde_json doesn't keep the order the package lists conditions in, use our own
+        serde_json::Value::Object(conditions) => CONDITIONS
+            .iter()
+            .filter_map(|condition| conditions.get(*condition))
+            .find_map(|it| resolve_export_target(it, matched)),
+        _ => None,
+    }
+}
+
 impl Resolver {
     fn wrap(&self, path: PathBuf) -> Result<FileName, Error> {
         let path = path.clean();
@@ -243,9 +531,74 @@ impl Resolver {
         self.resolve_index(path)
     }
 
-    /// Resolve using the package.json "main" key.
-    fn resolve_package_main(&self, _: &Path) -> Result<PathBuf, Error> {
-        bail!("package.json is not supported")
+    /// Resolve using the package.json "exports", "module" or "main" key.
+    fn resolve_package_main(&self, pkg_path: &Path) -> Result<PathBuf, Error> {
+        let pkg_dir = pkg_path.parent().unwrap_or_else(|| Path::new("."));
+        let pkg = read_package_json(pkg_path)?;
+
+        if let Some(exports) = pkg.get("exports") {
+            return self.resolve_package_export(pkg_dir, exports, ".");
+        }
+
+        // "module" points to an ES module build, which bundles better than "main"
+        for field in ["module", "main"] {
+            if let Some(main) = pkg.get(field).and_then(|it| it.as_str()) {
+                let path = pkg_dir.join(main);
+                let result = self
+                    .resolve_as_file(&path)
+                    .or_else(|_| self.resolve_index(&path));
+                if result.is_ok() {
+                    return result;
+                }
+            }
+        }
+
+        bail!("no entry point in {}", pkg_path.display())
+    }
+
+    /// Resolve `subpath` (`.` or `./name`) through the "exports" map of a package.
+    fn resolve_package_export(
+        &self,
+        pkg_dir: &Path,
+        exports: &serde_json::Value,
+        subpath: &str,
+    ) -> Result<PathBuf, Error> {
+        match resolve_exports(exports, subpath) {
+            // targets have to stay inside the package
+            Some(target)
+                if target.starts_with("./")
+                    && !Path::new(&target)
+                        .components()
+                        .any(|it| it == Component::ParentDir) =>
+            {
+                self.resolve_as_file(&pkg_dir.join(target))
+            }
+            _ => bail!("{} is not exported from {}", subpath, pkg_dir.display()),
+        }
+    }
+
+    /// Resolve a bare import (`name`, `@scope/name`, optionally followed by a
+    /// subpath) inside a node_modules directory.
+    fn resolve_package(&self, node_modules: &Path, target: &str) -> Result<PathBuf, Error> {
+        let (name, subpath) = split_package_specifier(target);
+        let pkg_dir = node_modules.join(name);
+        let pkg_path = pkg_dir.join("package.json");
+
+        if pkg_path.is_file() {
+            let pkg = read_package_json(&pkg_path)?;
+            // a package with an exports map hides every file it doesn't list
+            if let Some(exports) = pkg.get("exports") {
+                let subpath = match subpath {
+                    Some(subpath) => format!("./{}", subpath),
+                    None => ".".into(),
+                };
+                return self.resolve_package_export(&pkg_dir, exports, &subpath);
+            }
+        }
+
+        let path = node_modules.join(target);
+        self.resolve_as_file(&path)
+            .or_else(|_| self.resolve_as_directory(&path))
     }
 
     /// Resolve a directory to its index.EXT.
@@ -267,10 +620,7 @@ impl Resolver {
     fn resolve_node_modules(&self, base_dir: &Path, target: &str) -> Result<PathBuf, Error> {
         let node_modules = base_dir.join("node_modules");
         if node_modules.is_dir() {
-            let path = node_modules.join(target);
-            let result = self
-                .resolve_as_file(&path)
-                .or_else(|_| self.resolve_as_directory(&path));
+            let result = self.resolve_package(&node_modules, target);
             if result.is_ok() {
                 return result;
             }
@@ -326,11 +676,14 @@ impl Resolve for Resolver {
                 .and_then(|p| self.wrap(p));
         }
 
-        if let Some(import_map) = self.import_map.get(target) {
-            let cwd = std::env::current_dir().unwrap();
-            let base_js = cwd.join("./js");
+        if target == LAYOUTS_SPECIFIER {
+            return Ok(FileName::Real(self.layouts_module.clone()));
+        }
+
+        if let Some(path) = self.import_map.resolve(target) {
             return self
-                .resolve_as_file(&base_js.join(import_map))
+                .resolve_as_file(&path)
+                .or_else(|_| self.resolve_as_directory(&path))
                 .and_then(|p| self.wrap(p));
         }
 
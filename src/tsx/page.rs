@@ -0,0 +1,23 @@
+use axum::http::header::CONTENT_LANGUAGE;
+use axum::response::{Html, IntoResponse, Response};
+
+/// Precedes the markup of every page, React can't render it and browsers use
+/// quirks mode without it.
+const DOCTYPE: &str = "<!DOCTYPE html>";
+
+/// A rendered page, the markup of the document a route evaluated to.
+pub struct HtmlPage {
+    pub html: String,
+    /// sent as `Content-Language`
+    pub lang: String,
+}
+
+impl IntoResponse for HtmlPage {
+    fn into_response(self) -> Response {
+        (
+            [(CONTENT_LANGUAGE, self.lang)],
+            Html(format!("{}{}", DOCTYPE, self.html)),
+        )
+            .into_response()
+    }
+}